@@ -19,7 +19,8 @@ async fn dynamic_cooldowns(ctx: Context<'_>) -> Result<(), Error> {
             Some(remaining) => {
                 return Err(format!("Please wait {} seconds", remaining.as_secs()).into())
             }
-            None => cooldown_tracker.start_cooldown(ctx.cooldown_context()),
+            None => cooldown_tracker
+                .start_cooldown_with_config(ctx.cooldown_context(), &cooldown_durations),
         }
     };
 
@@ -98,6 +98,19 @@ pub async fn cooldowns(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+#[poise::command(
+    prefix_command,
+    slash_command,
+    // Five uses per minute per user, refilled one by one every 12 seconds
+    user_cooldown = 60,
+    user_cooldown_uses = 5,
+    cooldown_strategy = "TokenBucket"
+)]
+pub async fn rate_limited(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("You successfully called the command").await?;
+    Ok(())
+}
+
 #[poise::command(prefix_command, slash_command)]
 pub async fn minmax(
     ctx: Context<'_>,
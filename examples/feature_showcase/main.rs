@@ -46,6 +46,7 @@ async fn main() {
                 checks::delete(),
                 checks::ferrisparty(),
                 checks::cooldowns(),
+                checks::rate_limited(),
                 checks::minmax(),
                 checks::get_guild_name(),
                 checks::only_in_dms(),
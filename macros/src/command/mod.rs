@@ -27,7 +27,13 @@ pub struct CommandArgs {
     help_text_fn: Option<syn::Path>,
     #[darling(multiple)]
     check: Vec<syn::Path>,
+    #[darling(multiple)]
+    structured_check: Vec<syn::Path>,
+    #[darling(multiple)]
+    layer: Vec<syn::Path>,
     on_error: Option<syn::Path>,
+    // In seconds
+    timeout: Option<u64>,
     rename: Option<String>,
     #[darling(multiple)]
     name_localized: Vec<crate::util::Tuple2<String>>,
@@ -43,6 +49,10 @@ pub struct CommandArgs {
     guild_only: bool,
     dm_only: bool,
     nsfw_only: bool,
+    channel_kinds: crate::util::List<syn::Ident>,
+    required_roles: crate::util::List<syn::Lit>,
+    any_role: crate::util::List<syn::Lit>,
+    forbidden_roles: crate::util::List<syn::Lit>,
     identifying_name: Option<String>,
     category: Option<String>,
     custom_data: Option<syn::Expr>,
@@ -53,6 +63,18 @@ pub struct CommandArgs {
     guild_cooldown: Option<u64>,
     channel_cooldown: Option<u64>,
     member_cooldown: Option<u64>,
+    global_cooldown_uses: Option<u32>,
+    user_cooldown_uses: Option<u32>,
+    guild_cooldown_uses: Option<u32>,
+    channel_cooldown_uses: Option<u32>,
+    member_cooldown_uses: Option<u32>,
+    cooldown_strategy: Option<syn::Ident>,
+    cooldown_policy: Option<syn::Ident>,
+    cooldown_group: Option<String>,
+    max_concurrency: Option<u32>,
+    concurrency_scope: Option<syn::Ident>,
+    concurrency_queue: bool,
+    concurrency_queue_timeout: Option<u64>,
 }
 
 /// Representation of the function parameter attribute arguments
@@ -228,6 +250,22 @@ pub fn command(
     Ok(TokenStream::from(generate_command(inv)?))
 }
 
+/// Converts role IDs and names from the role attributes into `poise::RoleIdentifier`s
+fn role_identifiers(roles: &[syn::Lit]) -> Result<Vec<proc_macro2::TokenStream>, darling::Error> {
+    roles
+        .iter()
+        .map(|role| match role {
+            syn::Lit::Int(id) => Ok(quote::quote! {
+                ::poise::RoleIdentifier::Id(::poise::serenity_prelude::RoleId::new(#id))
+            }),
+            syn::Lit::Str(name) => Ok(quote::quote! {
+                ::poise::RoleIdentifier::Name(#name.to_string())
+            }),
+            _ => Err(darling::Error::custom("expected a role ID or name").with_span(role)),
+        })
+        .collect()
+}
+
 fn generate_command(mut inv: Invocation) -> Result<proc_macro2::TokenStream, darling::Error> {
     let ctx_type = match inv.function.sig.inputs.first() {
         Some(syn::FnArg::Typed(syn::PatType { ty, .. })) => &**ty,
@@ -285,6 +323,28 @@ fn generate_command(mut inv: Invocation) -> Result<proc_macro2::TokenStream, dar
     let guild_cooldown = wrap_option(inv.args.guild_cooldown);
     let channel_cooldown = wrap_option(inv.args.channel_cooldown);
     let member_cooldown = wrap_option(inv.args.member_cooldown);
+    let global_cooldown_uses = wrap_option(inv.args.global_cooldown_uses);
+    let user_cooldown_uses = wrap_option(inv.args.user_cooldown_uses);
+    let guild_cooldown_uses = wrap_option(inv.args.guild_cooldown_uses);
+    let channel_cooldown_uses = wrap_option(inv.args.channel_cooldown_uses);
+    let member_cooldown_uses = wrap_option(inv.args.member_cooldown_uses);
+    let cooldown_group = wrap_option_to_string(inv.args.cooldown_group.as_ref());
+    let cooldown_strategy = match &inv.args.cooldown_strategy {
+        Some(strategy) => quote::quote! { ::poise::CooldownStrategy::#strategy },
+        None => quote::quote! { ::poise::CooldownStrategy::SlidingWindow },
+    };
+    let cooldown_policy = match &inv.args.cooldown_policy {
+        Some(policy) => quote::quote! { ::poise::CooldownPolicy::#policy },
+        None => quote::quote! { ::poise::CooldownPolicy::Always },
+    };
+
+    let max_concurrency = wrap_option(inv.args.max_concurrency);
+    let concurrency_scope = match &inv.args.concurrency_scope {
+        Some(scope) => quote::quote! { ::poise::ConcurrencyScope::#scope },
+        None => quote::quote! { ::poise::ConcurrencyScope::Global },
+    };
+    let concurrency_queue = inv.args.concurrency_queue;
+    let concurrency_queue_timeout = wrap_option(inv.args.concurrency_queue_timeout);
 
     let default_member_permissions = &inv.default_member_permissions;
     let required_permissions = &inv.required_permissions;
@@ -294,6 +354,10 @@ fn generate_command(mut inv: Invocation) -> Result<proc_macro2::TokenStream, dar
     let guild_only = inv.args.guild_only;
     let dm_only = inv.args.dm_only;
     let nsfw_only = inv.args.nsfw_only;
+    let channel_kinds = &inv.args.channel_kinds.0;
+    let required_roles = role_identifiers(&inv.args.required_roles.0)?;
+    let any_role = role_identifiers(&inv.args.any_role.0)?;
+    let forbidden_roles = role_identifiers(&inv.args.forbidden_roles.0)?;
 
     let help_text = match &inv.args.help_text_fn {
         Some(help_text_fn) => quote::quote! { Some(#help_text_fn()) },
@@ -304,12 +368,16 @@ fn generate_command(mut inv: Invocation) -> Result<proc_macro2::TokenStream, dar
     };
 
     let checks = &inv.args.check;
+    let structured_checks = &inv.args.structured_check;
+    let layers = &inv.args.layer;
     // Box::pin the callback in order to store it in a struct
     let on_error = match &inv.args.on_error {
         Some(on_error) => quote::quote! { Some(|err| Box::pin(#on_error(err))) },
         None => quote::quote! { None },
     };
 
+    let timeout = wrap_option(inv.args.timeout);
+
     let invoke_on_edit = inv.args.invoke_on_edit || inv.args.track_edits;
     let reuse_response = inv.args.reuse_response || inv.args.track_edits;
     let track_deletion = inv.args.track_deletion || inv.args.track_edits;
@@ -341,6 +409,24 @@ fn generate_command(mut inv: Invocation) -> Result<proc_macro2::TokenStream, dar
         > {
             #function
 
+            let cooldown_config = ::poise::CooldownConfig {
+                global: #global_cooldown.map(std::time::Duration::from_secs),
+                user: #user_cooldown.map(std::time::Duration::from_secs),
+                guild: #guild_cooldown.map(std::time::Duration::from_secs),
+                channel: #channel_cooldown.map(std::time::Duration::from_secs),
+                member: #member_cooldown.map(std::time::Duration::from_secs),
+                global_uses: #global_cooldown_uses,
+                user_uses: #user_cooldown_uses,
+                guild_uses: #guild_cooldown_uses,
+                channel_uses: #channel_cooldown_uses,
+                member_uses: #member_cooldown_uses,
+                strategy: #cooldown_strategy,
+                exempt_roles: Vec::new(),
+                exempt_permissions: ::poise::serenity_prelude::Permissions::empty(),
+                role_overrides: Vec::new(),
+                policy: #cooldown_policy,
+                __non_exhaustive: ()
+            };
             ::poise::Command {
                 prefix_action: #prefix_action,
                 slash_action: #slash_action,
@@ -358,15 +444,19 @@ fn generate_command(mut inv: Invocation) -> Result<proc_macro2::TokenStream, dar
                 description_localizations: #description_localizations,
                 help_text: #help_text,
                 hide_in_help: #hide_in_help,
-                cooldowns: std::sync::Mutex::new(::poise::Cooldowns::new()),
-                cooldown_config: std::sync::RwLock::new(::poise::CooldownConfig {
-                    global: #global_cooldown.map(std::time::Duration::from_secs),
-                    user: #user_cooldown.map(std::time::Duration::from_secs),
-                    guild: #guild_cooldown.map(std::time::Duration::from_secs),
-                    channel: #channel_cooldown.map(std::time::Duration::from_secs),
-                    member: #member_cooldown.map(std::time::Duration::from_secs),
-                    __non_exhaustive: ()
+                cooldowns: std::sync::Mutex::new(
+                    ::poise::Cooldowns::new().with_config(cooldown_config.clone()),
+                ),
+                cooldown_config: std::sync::RwLock::new(cooldown_config),
+                cooldown_group: #cooldown_group,
+                max_concurrency: #max_concurrency.map(|limit| ::poise::MaxConcurrency {
+                    limit,
+                    scope: #concurrency_scope,
+                    queue: #concurrency_queue,
+                    queue_timeout: #concurrency_queue_timeout.map(std::time::Duration::from_secs),
+                    __non_exhaustive: (),
                 }),
+                concurrency: Default::default(),
                 reuse_response: #reuse_response,
                 default_member_permissions: #default_member_permissions,
                 required_permissions: #required_permissions,
@@ -375,8 +465,15 @@ fn generate_command(mut inv: Invocation) -> Result<proc_macro2::TokenStream, dar
                 guild_only: #guild_only,
                 dm_only: #dm_only,
                 nsfw_only: #nsfw_only,
+                channel_kinds: vec![ #( ::poise::ChannelKind::#channel_kinds, )* ],
+                required_roles: vec![ #( #required_roles, )* ],
+                any_role: vec![ #( #any_role, )* ],
+                forbidden_roles: vec![ #( #forbidden_roles, )* ],
                 checks: vec![ #( |ctx| Box::pin(#checks(ctx)) ),* ],
+                structured_checks: vec![ #( #structured_checks() ),* ],
+                layers: vec![ #( #layers() ),* ],
                 on_error: #on_error,
+                timeout: #timeout.map(std::time::Duration::from_secs),
                 parameters: vec![ #( #parameters ),* ],
                 custom_data: #custom_data,
 
@@ -63,10 +63,6 @@ pub fn generate_prefix_action(inv: &Invocation) -> Result<proc_macro2::TokenStre
                 error,
             ))?;
 
-            if !ctx.framework.options.manual_cooldowns {
-                ctx.command.cooldowns.lock().unwrap().start_cooldown(ctx.cooldown_context());
-            }
-
             inner(ctx.into(), #( #param_idents, )* )
                 .await
                 .map_err(|error| poise::FrameworkError::new_command(
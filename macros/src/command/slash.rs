@@ -190,10 +190,6 @@ pub fn generate_slash_action(inv: &Invocation) -> Result<proc_macro2::TokenStrea
                 #( (#param_names: #param_types), )*
             ).await.map_err(|error| error.to_framework_error(ctx))?;
 
-            if !ctx.framework.options.manual_cooldowns {
-                ctx.command.cooldowns.lock().unwrap().start_cooldown(ctx.cooldown_context());
-            }
-
             inner(ctx.into(), #( #param_identifiers, )*)
                 .await
                 .map_err(|error| poise::FrameworkError::new_command(
@@ -220,10 +216,6 @@ pub fn generate_context_menu_action(
     Ok(quote::quote! {
         <#param_type as ::poise::ContextMenuParameter<_, _>>::to_action(|ctx, value| {
             Box::pin(async move {
-                if !ctx.framework.options.manual_cooldowns {
-                    ctx.command.cooldowns.lock().unwrap().start_cooldown(ctx.cooldown_context());
-                }
-
                 inner(ctx.into(), value)
                     .await
                     .map_err(|error| poise::FrameworkError::new_command(
@@ -50,8 +50,13 @@ for example for command-specific help (i.e. `~help command_name`). Escape newlin
 - `guild_only`: Restricts command callers to only run on a guild
 - `dm_only`: Restricts command callers to only run on a DM
 - `nsfw_only`: Restricts command callers to only run on a NSFW channel
+- `channel_kinds`: Restricts the command to these kinds of channels: `"Dm"`, `"Text"`, `"News"`, `"Voice"`, `"Stage"`, `"Thread"` or `"ForumPost"`. See `poise::ChannelKind`
+- `required_roles`: Member must have all of these roles, given as role IDs or names, e.g. `required_roles("Moderator", 1234)`
+- `any_role`: Member must have at least one of these roles, given as role IDs or names
+- `forbidden_roles`: Member must have none of these roles, given as role IDs or names
 - `subcommand_required`: Requires a subcommand to be specified (prefix only)
 - `check`: Path to a function which is invoked for every invocation. If the function returns false, the command is not executed (can be used multiple times)
+- `structured_check`: Path to a function returning a `poise::Check`, which can give a reason when denying access and can be combined with other checks (can be used multiple times)
 
 ## Help-related arguments
 
@@ -73,10 +78,24 @@ for example for command-specific help (i.e. `~help command_name`). Escape newlin
 - `guild_cooldown`: Minimum duration in seconds between invocations, per guild
 - `channel_cooldown`: Minimum duration in seconds between invocations, per channel
 - `member_cooldown`: Minimum duration in seconds between invocations, per guild member
+- `global_cooldown_uses`, `user_cooldown_uses`, `guild_cooldown_uses`, `channel_cooldown_uses`, `member_cooldown_uses`: Allow this many invocations per the corresponding cooldown duration instead of just one
+    - For example, `user_cooldown = 60, user_cooldown_uses = 5` allows five invocations per user per minute
+- `cooldown_group`: Share cooldowns with all other commands in the same named group, e.g. `cooldown_group = "imagegen"`. Each command still applies its own cooldown durations to the shared history
+- `cooldown_strategy`: How multiple uses are spread over the cooldown duration: `"SlidingWindow"` (default) or `"TokenBucket"`. See `poise::CooldownStrategy`
+- `cooldown_policy`: Which outcomes count towards the cooldown: `"Always"` (default), `"OnSuccess"`, `"RefundOnError"` or `"RefundOnPanic"`. See `poise::CooldownPolicy`
+
+## Concurrency
+
+- `max_concurrency`: How many invocations of this command may run at the same time. See `poise::MaxConcurrency`
+- `concurrency_scope`: Which invocations share `max_concurrency`: `"Global"` (default), `"User"`, `"Guild"` or `"Channel"`
+- `concurrency_queue`: Make invocations exceeding `max_concurrency` wait instead of failing with `FrameworkError::ConcurrencyLimit`
+- `concurrency_queue_timeout`: How many seconds queued invocations wait at most. Defaults to the command timeout, see `poise::MaxConcurrency::queue_timeout`
 
 ## Other
 
 - `on_error`: Error handling function
+- `layer`: Path to a function returning a `poise::Layer`, which runs around the command action (can be used multiple times)
+- `timeout`: Cancel the command if it takes longer than this many seconds. Overrides `FrameworkOptions::command_timeout`
 - `broadcast_typing`: Trigger a typing indicator while command runs (prefix only)
 - `discard_spare_arguments`: Don't throw an error if the user supplies too many arguments (prefix only)
 - `ephemeral`: Make bot responses ephemeral if possible (slash only)
@@ -0,0 +1,164 @@
+//! Infrastructure for blocking users and guilds from using the bot at all
+
+use crate::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// A user or guild which can be blocked, see [`BlocklistStore`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum BlocklistTarget {
+    /// Blocks this user everywhere
+    User(serenity::UserId),
+    /// Blocks everyone in this guild
+    Guild(serenity::GuildId),
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+impl std::fmt::Display for BlocklistTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::User(user_id) => write!(f, "user {}", user_id),
+            Self::Guild(guild_id) => write!(f, "guild {}", guild_id),
+            Self::__NonExhaustive => unreachable!(),
+        }
+    }
+}
+
+/// A blocked user or guild
+///
+/// ```rust
+/// # use poise::serenity_prelude as serenity;
+/// let entry = poise::BlocklistEntry::new(poise::BlocklistTarget::User(serenity::UserId::new(1)))
+///     .reason("Spamming commands")
+///     .expires_at(std::time::SystemTime::now() + std::time::Duration::from_secs(24 * 60 * 60));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct BlocklistEntry {
+    /// Who is blocked
+    pub target: BlocklistTarget,
+    /// Why the target is blocked, e.g. to tell the user in
+    /// [`crate::FrameworkOptions::blocklist_message`]
+    pub reason: Option<String>,
+    /// When the block ends. None if permanent
+    pub expires_at: Option<SystemTime>,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl BlocklistEntry {
+    /// Creates a permanent block without a reason
+    pub fn new(target: BlocklistTarget) -> Self {
+        Self {
+            target,
+            reason: None,
+            expires_at: None,
+            __non_exhaustive: (),
+        }
+    }
+
+    /// Sets [`Self::reason`]
+    pub fn reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Sets [`Self::expires_at`]
+    pub fn expires_at(mut self, expires_at: SystemTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Whether the block has ended at the given point in time
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Storage backend for the blocklist, set via [`crate::FrameworkOptions::blocklist`].
+///
+/// The default is [`InMemoryBlocklistStore`], which forgets all entries on restart. Implement this
+/// trait on top of your database to persist them.
+///
+/// Only [`Self::get`], [`Self::insert`], [`Self::remove`] and [`Self::list`] need to be
+/// implemented. Expired entries are never returned by [`Self::blocked`], so stores may keep them
+/// around.
+#[async_trait::async_trait]
+pub trait BlocklistStore: Send + Sync {
+    /// Returns the entry of the given target, or None if it was never blocked
+    async fn get(&self, target: BlocklistTarget) -> Option<BlocklistEntry>;
+
+    /// Adds an entry, replacing any existing entry of the same target
+    async fn insert(&self, entry: BlocklistEntry);
+
+    /// Removes the entry of the given target, returning it if there was one
+    async fn remove(&self, target: BlocklistTarget) -> Option<BlocklistEntry>;
+
+    /// Returns all entries, including expired ones that haven't been removed yet
+    async fn list(&self) -> Vec<BlocklistEntry>;
+
+    /// Returns the entry which blocks the given user in the given guild, if any. User entries take
+    /// precedence over guild entries.
+    async fn blocked(
+        &self,
+        user_id: serenity::UserId,
+        guild_id: Option<serenity::GuildId>,
+    ) -> Option<BlocklistEntry> {
+        let now = SystemTime::now();
+        let targets = std::iter::once(BlocklistTarget::User(user_id))
+            .chain(guild_id.map(BlocklistTarget::Guild));
+        for target in targets {
+            if let Some(entry) = self.get(target).await {
+                if !entry.is_expired(now) {
+                    return Some(entry);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The default [`BlocklistStore`], which keeps all entries in memory
+///
+/// Expired entries are removed when they're looked up or listed.
+#[derive(Default, Debug)]
+pub struct InMemoryBlocklistStore {
+    /// All entries by target
+    entries: std::sync::Mutex<HashMap<BlocklistTarget, BlocklistEntry>>,
+}
+
+impl InMemoryBlocklistStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl BlocklistStore for InMemoryBlocklistStore {
+    async fn get(&self, target: BlocklistTarget) -> Option<BlocklistEntry> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(&target)?;
+        if entry.is_expired(SystemTime::now()) {
+            entries.remove(&target);
+            return None;
+        }
+        Some(entry.clone())
+    }
+
+    async fn insert(&self, entry: BlocklistEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(entry.target, entry);
+    }
+
+    async fn remove(&self, target: BlocklistTarget) -> Option<BlocklistEntry> {
+        self.entries.lock().unwrap().remove(&target)
+    }
+
+    async fn list(&self) -> Vec<BlocklistEntry> {
+        let now = SystemTime::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| !entry.is_expired(now));
+        entries.values().cloned().collect()
+    }
+}
@@ -0,0 +1,114 @@
+//! Building blocks for owner commands which manage [`crate::FrameworkOptions::blocklist`]
+
+use crate::serenity_prelude as serenity;
+use crate::CreateReply;
+
+/// Formats an expiry time as a relative Discord timestamp
+fn format_expiry(expires_at: Option<std::time::SystemTime>) -> String {
+    match expires_at {
+        Some(expires_at) => {
+            let unix = expires_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            format!("until <t:{}:R>", unix.as_secs())
+        }
+        None => "permanently".to_string(),
+    }
+}
+
+/// Blocks a user or guild from using the bot, optionally for a limited time and with a reason.
+/// Can only be used by bot owners.
+///
+/// ```rust,no_run
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # type Context<'a> = poise::Context<'a, (), Error>;
+/// # use poise::serenity_prelude as serenity;
+/// #[poise::command(prefix_command, owners_only, hide_in_help)]
+/// async fn block(
+///     ctx: Context<'_>,
+///     user: serenity::User,
+///     hours: Option<u64>,
+///     #[rest] reason: Option<String>,
+/// ) -> Result<(), Error> {
+///     let duration = hours.map(|hours| std::time::Duration::from_secs(hours * 60 * 60));
+///     let target = poise::BlocklistTarget::User(user.id);
+///     poise::builtins::blocklist_add(ctx, target, duration, reason).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn blocklist_add<U, E>(
+    ctx: crate::Context<'_, U, E>,
+    target: crate::BlocklistTarget,
+    duration: Option<std::time::Duration>,
+    reason: Option<String>,
+) -> Result<(), serenity::Error> {
+    if !super::ensure_owner(ctx).await? {
+        return Ok(());
+    }
+
+    let mut entry = crate::BlocklistEntry::new(target);
+    entry.reason = reason;
+    entry.expires_at = duration.map(|duration| std::time::SystemTime::now() + duration);
+    let response = format!("Blocked {} {}", target, format_expiry(entry.expires_at));
+    ctx.framework().options().blocklist.insert(entry).await;
+
+    ctx.send(CreateReply::default().content(response).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Unblocks a user or guild. Can only be used by bot owners.
+///
+/// See [`blocklist_add`] for an example.
+pub async fn blocklist_remove<U, E>(
+    ctx: crate::Context<'_, U, E>,
+    target: crate::BlocklistTarget,
+) -> Result<(), serenity::Error> {
+    if !super::ensure_owner(ctx).await? {
+        return Ok(());
+    }
+
+    let response = match ctx.framework().options().blocklist.remove(target).await {
+        Some(_) => format!("Unblocked {}", target),
+        None => format!("{} wasn't blocked", target),
+    };
+
+    ctx.send(CreateReply::default().content(response).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Lists all blocked users and guilds with their expiry and reason. Can only be used by bot
+/// owners.
+///
+/// Example:
+/// > 2 blocklist entries:
+/// > - user 1234 permanently: Spamming commands
+/// > - guild 5678 until in 3 days
+pub async fn blocklist_list<U, E>(ctx: crate::Context<'_, U, E>) -> Result<(), serenity::Error> {
+    if !super::ensure_owner(ctx).await? {
+        return Ok(());
+    }
+
+    let entries = ctx.framework().options().blocklist.list().await;
+    let mut response = format!("{} blocklist entries:\n", entries.len());
+    for entry in entries {
+        let mut line = format!("- {} {}", entry.target, format_expiry(entry.expires_at));
+        if let Some(reason) = &entry.reason {
+            line += ": ";
+            line += reason;
+        }
+        line += "\n";
+
+        // Stay below the 2000 char message limit
+        if response.len() + line.len() > 1980 {
+            response += "- ...";
+            break;
+        }
+        response += &line;
+    }
+
+    ctx.send(CreateReply::default().content(response).ephemeral(true))
+        .await?;
+    Ok(())
+}
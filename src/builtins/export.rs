@@ -0,0 +1,197 @@
+//! Utilities for describing the command tree as JSON, e.g. for web command lists or snapshot tests
+
+use crate::serenity_prelude as serenity;
+
+/// Describes all given commands and their subcommands as a JSON array, without needing a Discord
+/// connection.
+///
+/// Each command object contains its names, aliases, category, descriptions and localizations,
+/// which invocation kinds it supports, permissions and restrictions, cooldown config, concurrency
+/// limit, timeout, the number of checks, its parameters (with slash argument type, constraints and
+/// choices) and its nested subcommands. Localization maps are sorted by locale, so the output is
+/// stable across runs and suitable for snapshot testing.
+///
+/// The format is meant for humans and tools to read; it's not accepted by Discord. For the exact
+/// payloads that are sent to Discord on registration, see [`export_application_commands`].
+///
+/// ```rust
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # type Context<'a> = poise::Context<'a, (), Error>;
+/// /// Repeats a message
+/// #[poise::command(prefix_command, slash_command, aliases("say"), category = "Fun")]
+/// async fn echo(
+///     ctx: Context<'_>,
+///     #[description = "What to repeat"] message: String,
+/// ) -> Result<(), Error> { Ok(()) }
+///
+/// let mut commands = vec![echo()];
+/// poise::set_qualified_names(&mut commands);
+/// let tree = poise::builtins::export_commands(&commands);
+///
+/// assert_eq!(tree[0]["qualified_name"], "echo");
+/// assert_eq!(tree[0]["aliases"][0], "say");
+/// assert_eq!(tree[0]["slash_command"], true);
+/// assert_eq!(tree[0]["parameters"][0]["name"], "message");
+/// assert_eq!(tree[0]["parameters"][0]["type"], 3); // String
+/// ```
+pub fn export_commands<U, E>(commands: &[crate::Command<U, E>]) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    json!(commands.iter().map(export_command).collect::<Vec<_>>())
+}
+
+/// Serializes the payloads that [`crate::builtins::create_application_commands`] would send to
+/// Discord on registration, as a JSON array.
+///
+/// Useful to review changes to the registered slash command schema, e.g. by committing the output
+/// and diffing it in pull requests.
+pub fn export_application_commands<U, E>(
+    commands: &[crate::Command<U, E>],
+) -> serenity::json::Value {
+    serenity::json::to_value(crate::builtins::create_application_commands(commands))
+        .expect("command builders always serialize to JSON")
+}
+
+/// See [`export_commands`]
+fn export_command<U, E>(command: &crate::Command<U, E>) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    let context_menu = match command.context_menu_action {
+        Some(crate::ContextMenuCommandAction::User(_)) => Some("user"),
+        Some(crate::ContextMenuCommandAction::Message(_)) => Some("message"),
+        _ => None,
+    };
+
+    let mut value = json!({
+        "name": command.name,
+        "qualified_name": command.qualified_name,
+        "identifying_name": command.identifying_name,
+        "aliases": command.aliases,
+        "category": command.category,
+        "hide_in_help": command.hide_in_help,
+        "description": command.description,
+        "help_text": command.help_text,
+        "name_localizations": sorted(&command.name_localizations),
+        "description_localizations": sorted(&command.description_localizations),
+        "prefix_command": command.prefix_action.is_some(),
+        "slash_command": command.slash_action.is_some(),
+        "context_menu_command": context_menu,
+        "context_menu_name": command.context_menu_name,
+        "subcommand_required": command.subcommand_required,
+        "parameters": command.parameters.iter().map(export_parameter).collect::<Vec<_>>(),
+        "subcommands": command.subcommands.iter().map(export_command).collect::<Vec<_>>(),
+    });
+    value["restrictions"] = export_restrictions(command);
+    value["cooldowns"] = export_cooldowns(&command.cooldown_config.read().unwrap());
+    value["cooldown_group"] = json!(command.cooldown_group);
+    value["max_concurrency"] = json!(command.max_concurrency.as_ref().map(|m| {
+        json!({ "limit": m.limit, "scope": format!("{:?}", m.scope), "queue": m.queue })
+    }));
+    value["timeout"] = json!(command.timeout.map(|d| d.as_secs_f64()));
+    value["checks"] = json!(command.checks.len() + command.structured_checks.len());
+    value
+}
+
+/// See [`export_commands`]
+fn export_restrictions<U, E>(command: &crate::Command<U, E>) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    json!({
+        "default_member_permissions": command.default_member_permissions.get_permission_names(),
+        "required_permissions": command.required_permissions.get_permission_names(),
+        "required_bot_permissions": command.required_bot_permissions.get_permission_names(),
+        "owners_only": command.owners_only,
+        "guild_only": command.guild_only,
+        "dm_only": command.dm_only,
+        "nsfw_only": command.nsfw_only,
+        "channel_kinds": command.channel_kinds.iter().map(|k| format!("{:?}", k)).collect::<Vec<_>>(),
+        "required_roles": command.required_roles.iter().map(export_role).collect::<Vec<_>>(),
+        "any_role": command.any_role.iter().map(export_role).collect::<Vec<_>>(),
+        "forbidden_roles": command.forbidden_roles.iter().map(export_role).collect::<Vec<_>>(),
+        "ephemeral": command.ephemeral,
+    })
+}
+
+/// See [`export_commands`]
+fn export_role(role: &crate::RoleIdentifier) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    match role {
+        crate::RoleIdentifier::Id(role_id) => json!({ "id": role_id }),
+        crate::RoleIdentifier::Name(name) => json!({ "name": name }),
+        crate::RoleIdentifier::__NonExhaustive => unreachable!(),
+    }
+}
+
+/// See [`export_commands`]. Durations are in seconds
+fn export_cooldowns(config: &crate::CooldownConfig) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    let seconds = |duration: Option<std::time::Duration>| duration.map(|d| d.as_secs_f64());
+    json!({
+        "global": seconds(config.global),
+        "user": seconds(config.user),
+        "guild": seconds(config.guild),
+        "channel": seconds(config.channel),
+        "member": seconds(config.member),
+        "global_uses": config.global_uses,
+        "user_uses": config.user_uses,
+        "guild_uses": config.guild_uses,
+        "channel_uses": config.channel_uses,
+        "member_uses": config.member_uses,
+        "strategy": format!("{:?}", config.strategy),
+        "policy": format!("{:?}", config.policy),
+        "exempt_roles": config.exempt_roles,
+        "exempt_permissions": config.exempt_permissions.get_permission_names(),
+        "role_overrides": config.role_overrides.iter().map(|o| {
+            json!({ "role_id": o.role_id, "duration_percent": o.duration_percent })
+        }).collect::<Vec<_>>(),
+    })
+}
+
+/// See [`export_commands`]. The slash argument type and constraints are taken from the serialized
+/// [`serenity::CreateCommandOption`] and are null for parameters that aren't slash-compatible
+fn export_parameter<U, E>(parameter: &crate::CommandParameter<U, E>) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    let option = parameter
+        .create_as_slash_command_option()
+        .and_then(|option| to_value(option).ok())
+        .unwrap_or(NULL);
+    let option_field = |key| option.get(key).cloned().unwrap_or(NULL);
+
+    let choices = parameter.choices.iter().map(|choice| {
+        json!({
+            "name": choice.name,
+            "localizations": sorted(&choice.localizations),
+        })
+    });
+    json!({
+        "name": parameter.name,
+        "description": parameter.description,
+        "name_localizations": sorted(&parameter.name_localizations),
+        "description_localizations": sorted(&parameter.description_localizations),
+        "required": parameter.required,
+        "autocomplete": parameter.autocomplete_callback.is_some(),
+        "type": option_field("type"),
+        "min_value": option_field("min_value"),
+        "max_value": option_field("max_value"),
+        "min_length": option_field("min_length"),
+        "max_length": option_field("max_length"),
+        "channel_types": parameter.channel_types,
+        "choices": choices.collect::<Vec<_>>(),
+    })
+}
+
+/// Sorts a localization map by locale, for deterministic output
+fn sorted(
+    map: &std::collections::HashMap<String, String>,
+) -> std::collections::BTreeMap<&str, &str> {
+    map.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+}
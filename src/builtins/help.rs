@@ -101,6 +101,18 @@ pub(super) async fn get_prefix_from_options<U, E>(ctx: crate::Context<'_, U, E>)
     }
 }
 
+/// Whether the command isn't disabled via [`crate::FrameworkOptions::registry`] where help was
+/// invoked. Parent commands have to be checked separately
+pub(super) fn is_enabled<U, E>(
+    ctx: crate::Context<'_, U, E>,
+    command: &crate::Command<U, E>,
+) -> bool {
+    ctx.framework()
+        .options()
+        .registry
+        .is_enabled(&command.qualified_name, ctx.guild_id())
+}
+
 /// Format context menu command name
 fn format_context_menu_name<U, E>(command: &crate::Command<U, E>) -> Option<String> {
     let kind = match command.context_menu_action {
@@ -125,9 +137,12 @@ async fn help_single_command<U, E>(
     command_name: &str,
     config: HelpConfiguration<'_>,
 ) -> Result<(), serenity::Error> {
-    let commands = &ctx.framework().options().commands;
+    let commands = ctx.framework().options().active_commands();
     // Try interpret the command name as a context menu command first
     let mut command = commands.iter().find(|command| {
+        if !is_enabled(ctx, command) {
+            return false;
+        }
         if let Some(context_menu_name) = &command.context_menu_name {
             if context_menu_name.eq_ignore_ascii_case(command_name) {
                 return true;
@@ -137,8 +152,17 @@ async fn help_single_command<U, E>(
     });
     // Then interpret command name as a normal command (possibly nested subcommand)
     if command.is_none() {
-        if let Some((c, _, _)) = crate::find_command(commands, command_name, true, &mut vec![]) {
-            command = Some(c);
+        let mut parent_commands = vec![];
+        if let Some((c, _, _)) =
+            crate::find_command(&commands, command_name, true, &mut parent_commands)
+        {
+            if parent_commands
+                .iter()
+                .chain([&c])
+                .all(|c| is_enabled(ctx, c))
+            {
+                command = Some(c);
+            }
         }
     }
 
@@ -213,6 +237,7 @@ async fn help_single_command<U, E>(
             // hierarchy in the menu, so just display them as a list without
             // subprefix.
             preformat_subcommands(
+                ctx,
                 &mut commandlist,
                 command,
                 &subprefix.unwrap_or_else(|| String::from("  ")),
@@ -235,12 +260,16 @@ async fn help_single_command<U, E>(
 
 /// Recursively formats all subcommands
 fn preformat_subcommands<U, E>(
+    ctx: crate::Context<'_, U, E>,
     commands: &mut TwoColumnList,
     command: &crate::Command<U, E>,
     prefix: &str,
 ) {
     let as_context_command = command.slash_action.is_none() && command.prefix_action.is_none();
     for subcommand in &command.subcommands {
+        if !is_enabled(ctx, subcommand) {
+            continue;
+        }
         let command = if as_context_command {
             let name = format_context_menu_name(subcommand);
             if name.is_none() {
@@ -260,6 +289,7 @@ fn preformat_subcommands<U, E>(
 
 /// Preformat lines (except for padding,) like `("  /ping", "Emits a ping message")`
 fn preformat_command<U, E>(
+    ctx: crate::Context<'_, U, E>,
     commands: &mut TwoColumnList,
     config: &HelpConfiguration<'_>,
     command: &crate::Command<U, E>,
@@ -282,7 +312,7 @@ fn preformat_command<U, E>(
         command.description.as_deref().unwrap_or("").to_string(),
     );
     if config.show_subcommands {
-        preformat_subcommands(commands, command, &prefix)
+        preformat_subcommands(ctx, commands, command, &prefix)
     }
 }
 
@@ -294,7 +324,8 @@ async fn generate_all_commands<U, E>(
     config: &HelpConfiguration<'_>,
 ) -> Result<String, serenity::Error> {
     let mut categories = crate::util::OrderedMap::<Option<&str>, Vec<&crate::Command<U, E>>>::new();
-    for cmd in &ctx.framework().options().commands {
+    let commands = ctx.framework().options().active_commands();
+    for cmd in commands.iter().filter(|cmd| is_enabled(ctx, cmd)) {
         categories
             .get_or_insert_with(cmd.category.as_deref(), Vec::new)
             .push(cmd);
@@ -318,6 +349,7 @@ async fn generate_all_commands<U, E>(
         commandlist.push_heading(category_name.unwrap_or("Commands"));
         for command in commands {
             preformat_command(
+                ctx,
                 &mut commandlist,
                 config,
                 command,
@@ -331,7 +363,10 @@ async fn generate_all_commands<U, E>(
     if config.show_context_menu_commands {
         menu += "\nContext menu commands:\n";
 
-        for command in &ctx.framework().options().commands {
+        for command in &commands {
+            if !is_enabled(ctx, command) {
+                continue;
+            }
             let name = format_context_menu_name(command);
             if name.is_none() {
                 continue;
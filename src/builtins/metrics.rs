@@ -0,0 +1,102 @@
+//! Building block for an owner command which shows the collected [`crate::MetricsSnapshot`]
+
+use crate::serenity_prelude as serenity;
+use crate::CreateReply;
+
+/// Formats an optional duration in whole milliseconds
+fn format_millis(duration: Option<std::time::Duration>) -> String {
+    match duration {
+        Some(duration) => format!("{}ms", duration.as_millis()),
+        None => "-".to_string(),
+    }
+}
+
+/// Renders the metrics collected by [`crate::FrameworkOptions::metrics`]: invocations, failures,
+/// argument parse failures, cooldown hits and latency percentiles per command, errors per
+/// [`crate::FrameworkError`] variant and the edit tracker size. Can only be used by bot owners.
+///
+/// ```rust,no_run
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # type Context<'a> = poise::Context<'a, (), Error>;
+/// #[poise::command(prefix_command, slash_command, owners_only, hide_in_help)]
+/// async fn metrics(ctx: Context<'_>) -> Result<(), Error> {
+///     poise::builtins::metrics(ctx).await?;
+///     Ok(())
+/// }
+/// ```
+///
+/// Example:
+/// ```text
+/// Command  Calls  Fails  Parse  Cooldown  p50     p95     Max
+/// ping        12      1      0         1  5ms     25ms    31ms
+///
+/// Errors: CooldownHit 1
+/// Edit tracker: 3 messages
+/// ```
+pub async fn metrics<U, E>(ctx: crate::Context<'_, U, E>) -> Result<(), serenity::Error> {
+    if !super::ensure_owner(ctx).await? {
+        return Ok(());
+    }
+
+    let snapshot = match ctx.framework().options().metrics.snapshot() {
+        Some(x) => x,
+        None => {
+            let response = "The configured metrics sink doesn't keep metrics in memory";
+            ctx.send(CreateReply::default().content(response).ephemeral(true))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let name_width = snapshot
+        .commands
+        .keys()
+        .map(|name| name.chars().count())
+        .chain(std::iter::once("Command".len()))
+        .max()
+        .unwrap_or_default();
+    let mut table = format!(
+        "{:name_width$}  Calls  Fails  Parse  Cooldown  p50     p95     Max\n",
+        "Command"
+    );
+    for (name, command) in &snapshot.commands {
+        let latency = &command.latency;
+        let line = format!(
+            "{:name_width$}  {:>5}  {:>5}  {:>5}  {:>8}  {:7} {:7} {}\n",
+            name,
+            command.invocations,
+            command.failures,
+            command.argument_parse_failures,
+            command.cooldown_hits,
+            format_millis(latency.quantile(0.5)),
+            format_millis(latency.quantile(0.95)),
+            format_millis((latency.count > 0).then_some(latency.max)),
+        );
+
+        // Stay below the 2000 char message limit
+        if table.len() + line.len() > 1900 {
+            table += "...\n";
+            break;
+        }
+        table += &line;
+    }
+
+    let errors = snapshot
+        .errors
+        .iter()
+        .map(|(variant, count)| format!("{} {}", variant, count))
+        .collect::<Vec<_>>();
+    let errors = match errors.is_empty() {
+        true => "none".to_string(),
+        false => errors.join(", "),
+    };
+    let footer = format!(
+        "\nErrors: {}\nEdit tracker: {} messages\n",
+        errors, snapshot.edit_tracker_size
+    );
+
+    let response = format!("```\n{}{}```", table, footer);
+    ctx.send(CreateReply::default().content(response).ephemeral(true))
+        .await?;
+    Ok(())
+}
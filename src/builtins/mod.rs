@@ -12,6 +12,15 @@ pub use pretty_help::*;
 mod register;
 pub use register::*;
 
+mod export;
+pub use export::*;
+
+mod blocklist;
+pub use blocklist::*;
+
+mod metrics;
+pub use metrics::*;
+
 #[cfg(any(feature = "chrono", feature = "time"))]
 mod paginate;
 #[cfg(any(feature = "chrono", feature = "time"))]
@@ -19,6 +28,18 @@ pub use paginate::*;
 
 use crate::{serenity_prelude as serenity, CreateReply};
 
+/// Responds ephemerally and returns false if the author isn't a bot owner
+async fn ensure_owner<U, E>(ctx: crate::Context<'_, U, E>) -> Result<bool, serenity::Error> {
+    if ctx.framework().options().owners.contains(&ctx.author().id) {
+        return Ok(true);
+    }
+    let reply = CreateReply::default()
+        .content("Can only be used by bot owner")
+        .ephemeral(true);
+    ctx.send(reply).await?;
+    Ok(false)
+}
+
 /// An error handler that logs errors either via the [`tracing`] crate or via a Discord message. Set
 /// up a logger (e.g. `env_logger::init()`) or a tracing subscriber
 /// (e.g. `tracing_subscriber::fmt::init()`) to see the logged errors from this method.
@@ -66,6 +87,14 @@ pub async fn on_error<U, E: std::fmt::Display + std::fmt::Debug>(
             ctx.send(CreateReply::default().content(response).ephemeral(true))
                 .await?;
         }
+        crate::FrameworkError::CommandTimeout { ctx, timeout: _ } => {
+            let response = format!(
+                "`{}` took too long and was cancelled",
+                ctx.command().qualified_name
+            );
+            ctx.send(CreateReply::default().content(response).ephemeral(true))
+                .await?;
+        }
         crate::FrameworkError::CommandPanic { ctx, payload: _ } => {
             // Not showing the payload to the user because it may contain sensitive info
             let embed = serenity::CreateEmbed::default()
@@ -100,7 +129,50 @@ pub async fn on_error<U, E: std::fmt::Display + std::fmt::Debug>(
                 description,
             );
         }
-        crate::FrameworkError::CommandCheckFailed { ctx, error } => {
+        crate::FrameworkError::ChannelKindNotAllowed {
+            allowed_kinds, ctx, ..
+        } => {
+            let allowed_kinds = allowed_kinds.iter().map(|kind| kind.to_string());
+            let response = format!(
+                "`{}{}` can only be used in {}",
+                ctx.prefix(),
+                ctx.command().name,
+                allowed_kinds.collect::<Vec<_>>().join(", "),
+            );
+            ctx.send(CreateReply::default().content(response).ephemeral(true))
+                .await?;
+        }
+        crate::FrameworkError::ChannelNotAllowed { ctx, .. } => {
+            let response = format!(
+                "`{}{}` can't be used in this channel",
+                ctx.prefix(),
+                ctx.command().name,
+            );
+            ctx.send(CreateReply::default().content(response).ephemeral(true))
+                .await?;
+        }
+        crate::FrameworkError::CommandDisabled { ctx, .. } => {
+            let response = format!(
+                "`{}{}` is currently disabled",
+                ctx.prefix(),
+                ctx.command().name,
+            );
+            ctx.send(CreateReply::default().content(response).ephemeral(true))
+                .await?;
+        }
+        crate::FrameworkError::CommandCheckFailed {
+            ctx,
+            denial: Some(denial),
+            ..
+        } => {
+            let response = match denial.message {
+                Some(message) => message,
+                None => format!("You can't use this command right now ({})", denial.key),
+            };
+            ctx.send(CreateReply::default().content(response).ephemeral(true))
+                .await?;
+        }
+        crate::FrameworkError::CommandCheckFailed { ctx, error, .. } => {
             tracing::error!(
                 "A command check failed in command {} for user {}: {:?}",
                 ctx.command().name,
@@ -110,12 +182,34 @@ pub async fn on_error<U, E: std::fmt::Display + std::fmt::Debug>(
         }
         crate::FrameworkError::CooldownHit {
             remaining_cooldown,
+            cooldown_group,
             ctx,
+            ..
         } => {
-            let msg = format!(
+            let mut msg = format!(
                 "You're too fast. Please wait {} seconds before retrying",
                 remaining_cooldown.as_secs()
             );
+            if let Some(cooldown_group) = cooldown_group {
+                msg += &format!(" (cooldown shared by all `{}` commands)", cooldown_group);
+            }
+            ctx.send(CreateReply::default().content(msg).ephemeral(true))
+                .await?;
+        }
+        crate::FrameworkError::ConcurrencyLimit {
+            max_concurrency,
+            ctx,
+        } => {
+            let msg = match max_concurrency.queue {
+                true => format!(
+                    "Too many `{}` invocations are running. Please try again later",
+                    ctx.command().qualified_name
+                ),
+                false => format!(
+                    "Too many `{}` invocations are already running. Please wait for them to finish",
+                    ctx.command().qualified_name
+                ),
+            };
             ctx.send(CreateReply::default().content(msg).ephemeral(true))
                 .await?;
         }
@@ -151,6 +245,45 @@ pub async fn on_error<U, E: std::fmt::Display + std::fmt::Debug>(
             ctx.send(CreateReply::default().content(response).ephemeral(true))
                 .await?;
         }
+        crate::FrameworkError::MissingRoles {
+            missing_roles,
+            missing_any_role,
+            forbidden_roles,
+            ctx,
+            ..
+        } => {
+            let list = |roles: &[crate::RoleIdentifier]| {
+                roles
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let mut reasons = Vec::new();
+            if !missing_roles.is_empty() {
+                reasons.push(format!("you need the roles {}", list(&missing_roles)));
+            }
+            if !missing_any_role.is_empty() {
+                reasons.push(format!(
+                    "you need one of the roles {}",
+                    list(&missing_any_role)
+                ));
+            }
+            if !forbidden_roles.is_empty() {
+                reasons.push(format!(
+                    "you may not have the roles {}",
+                    list(&forbidden_roles)
+                ));
+            }
+            let response = format!(
+                "You can't use `{}{}`: {}",
+                ctx.prefix(),
+                ctx.command().name,
+                reasons.join("; "),
+            );
+            ctx.send(CreateReply::default().content(response).ephemeral(true))
+                .await?;
+        }
         crate::FrameworkError::NotAnOwner { ctx } => {
             let response = "Only bot owners can call this command";
             ctx.send(CreateReply::default().content(response).ephemeral(true))
@@ -195,6 +328,28 @@ pub async fn on_error<U, E: std::fmt::Display + std::fmt::Debug>(
         crate::FrameworkError::NonCommandMessage { error, .. } => {
             tracing::warn!("error in non-command message handler: {}", error);
         }
+        crate::FrameworkError::ComponentHandler { ctx, error } => {
+            let error = error.to_string();
+            eprintln!("An error occured in a component handler: {}", error);
+            ctx.send(CreateReply::default().content(error).ephemeral(true))
+                .await?;
+        }
+        crate::FrameworkError::ModalParse { ctx, error } => {
+            tracing::warn!(
+                "failed to parse submission of modal `{}`: {}",
+                ctx.custom_id(),
+                error
+            );
+            let response = format!("**Invalid form submission: {}**", error);
+            ctx.send(CreateReply::default().content(response).ephemeral(true))
+                .await?;
+        }
+        crate::FrameworkError::ModalHandler { ctx, error } => {
+            let error = error.to_string();
+            eprintln!("An error occured in a modal handler: {}", error);
+            ctx.send(CreateReply::default().content(error).ephemeral(true))
+                .await?;
+        }
         crate::FrameworkError::__NonExhaustive(unreachable) => match unreachable {},
     }
 
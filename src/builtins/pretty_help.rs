@@ -55,8 +55,10 @@ async fn pretty_help_all_commands<U, E>(
     config: PrettyHelpConfiguration<'_>,
 ) -> Result<(), serenity::Error> {
     let mut categories = crate::util::OrderedMap::new();
-    let commands = ctx.framework().options().commands.iter().filter(|cmd| {
-        !cmd.hide_in_help
+    let commands = ctx.framework().options().active_commands();
+    let commands = commands.iter().filter(|cmd| {
+        super::help::is_enabled(ctx, cmd)
+            && !cmd.hide_in_help
             && (cmd.prefix_action.is_some()
                 || cmd.slash_action.is_some()
                 || (cmd.context_menu_action.is_some() && config.show_context_menu_commands))
@@ -91,6 +93,9 @@ async fn pretty_help_all_commands<U, E>(
 
                 if config.show_subcommands {
                     for sbcmd in &cmd.subcommands {
+                        if !super::help::is_enabled(ctx, sbcmd) {
+                            continue;
+                        }
                         let name = sbcmd.context_menu_name.as_deref().unwrap_or(&sbcmd.name);
                         let prefix = format_cmd_prefix(sbcmd, &options_prefix);
 
@@ -151,18 +156,27 @@ async fn pretty_help_single_command<U, E>(
     command_name: &str,
     config: PrettyHelpConfiguration<'_>,
 ) -> Result<(), serenity::Error> {
-    let commands = &ctx.framework().options().commands;
+    let commands = ctx.framework().options().active_commands();
 
     // Try interpret the command name as a context menu command first
     let command = commands
         .iter()
         .find(|cmd| {
-            cmd.context_menu_name
-                .as_ref()
-                .is_some_and(|n| n.eq_ignore_ascii_case(command_name))
+            super::help::is_enabled(ctx, cmd)
+                && cmd
+                    .context_menu_name
+                    .as_ref()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(command_name))
         })
         // Then interpret command name as a normal command (possibly nested subcommand)
-        .or(crate::find_command(commands, command_name, true, &mut vec![]).map(|(c, _, _)| c));
+        .or_else(|| {
+            let mut parent_commands = vec![];
+            let (command, _, _) =
+                crate::find_command(&commands, command_name, true, &mut parent_commands)?;
+            let is_enabled = (parent_commands.iter().copied().chain([command]))
+                .all(|c| super::help::is_enabled(ctx, c));
+            is_enabled.then_some(command)
+        });
 
     let Some(command) = command else {
         ctx.send(
@@ -246,6 +260,7 @@ async fn pretty_help_single_command<U, E>(
     let sbcmds = command
         .subcommands
         .iter()
+        .filter(|sbcmd| super::help::is_enabled(ctx, sbcmd))
         .map(|sbcmd| {
             let prefix = format_cmd_prefix(sbcmd, &subprefix); // i have no idea about this really
             let name = sbcmd.context_menu_name.as_deref().unwrap_or(&sbcmd.name);
@@ -16,8 +16,8 @@ use crate::serenity_prelude as serenity;
 /// serenity::Command::set_global_commands(ctx, create_commands).await?;
 /// # Ok(()) }
 /// ```
-pub fn create_application_commands<U, E>(
-    commands: &[crate::Command<U, E>],
+pub fn create_application_commands<'a, U: 'a, E: 'a>(
+    commands: impl IntoIterator<Item = &'a crate::Command<U, E>>,
 ) -> Vec<serenity::CreateCommand> {
     /// We decided to extract context menu commands recursively, despite the subcommand hierarchy
     /// not being preserved. Because it's more confusing to just silently discard context menu
@@ -35,7 +35,7 @@ pub fn create_application_commands<U, E>(
         }
     }
 
-    let mut commands_builder = Vec::with_capacity(commands.len());
+    let mut commands_builder = Vec::new();
     for command in commands {
         if let Some(slash_command) = command.create_as_slash_command() {
             commands_builder.push(slash_command);
@@ -95,7 +95,8 @@ pub async fn register_application_commands<U, E>(
         return Ok(());
     }
 
-    let commands_builder = create_application_commands(&ctx.framework().options().commands);
+    let commands_builder =
+        create_application_commands(&ctx.framework().options().active_commands());
     let num_commands = commands_builder.len();
 
     if global {
@@ -151,7 +152,7 @@ pub async fn register_application_commands<U, E>(
 pub async fn register_application_commands_buttons<U, E>(
     ctx: crate::Context<'_, U, E>,
 ) -> Result<(), serenity::Error> {
-    let create_commands = create_application_commands(&ctx.framework().options().commands);
+    let create_commands = create_application_commands(&ctx.framework().options().active_commands());
     let num_commands = create_commands.len();
 
     let is_bot_owner = ctx.framework().options().owners.contains(&ctx.author().id);
@@ -263,3 +264,264 @@ pub async fn register_application_commands_buttons<U, E>(
 
     Ok(())
 }
+
+/// Summary of the changes made by [`sync_application_commands`]
+#[derive(Clone, Debug, Default)]
+pub struct CommandSyncReport {
+    /// Names of commands which weren't registered yet and were created
+    pub created: Vec<String>,
+    /// Names of registered commands which differed from the local definition and were edited
+    pub edited: Vec<String>,
+    /// Names of registered commands which don't exist locally anymore and were deleted
+    pub deleted: Vec<String>,
+    /// Names of registered commands which were already up to date
+    pub unchanged: Vec<String>,
+    // #[non_exhaustive] forbids struct update syntax for ?? reason
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl CommandSyncReport {
+    /// Whether no API calls besides fetching the registered commands had to be made
+    pub fn is_unchanged(&self) -> bool {
+        self.created.is_empty() && self.edited.is_empty() && self.deleted.is_empty()
+    }
+}
+
+impl std::fmt::Display for CommandSyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} created, {} edited, {} deleted, {} unchanged",
+            self.created.len(),
+            self.edited.len(),
+            self.deleted.len(),
+            self.unchanged.len()
+        )
+    }
+}
+
+/// Registers the given list of application commands to Discord, globally if `guild_id` is None,
+/// but only sends the changes.
+///
+/// Unlike [`register_globally`] and [`register_in_guild`], which overwrite all commands in bulk,
+/// this fetches the currently registered commands and compares them with the output of
+/// [`create_application_commands`]: names, descriptions, localizations, parameters, choices,
+/// default member permissions, DM permission and NSFW status. Then, only the commands that are new,
+/// changed or removed are created, edited or deleted. This is faster and avoids Discord's daily
+/// command creation limit when only few commands change, for example when syncing on every bot
+/// startup.
+///
+/// To sync the commands changed at runtime via [`crate::CommandRegistry`], pass
+/// [`crate::FrameworkOptions::active_commands`].
+///
+/// ```rust,no_run
+/// # async fn foo(ctx: poise::Context<'_, (), ()>) -> Result<(), poise::serenity_prelude::Error> {
+/// let commands = &ctx.framework().options().commands;
+/// let report = poise::builtins::sync_application_commands(ctx, commands, ctx.guild_id()).await?;
+/// ctx.say(format!("Synced commands: {}", report)).await?;
+/// # Ok(()) }
+/// ```
+pub async fn sync_application_commands<'a, U: 'a, E: 'a>(
+    http: impl AsRef<serenity::Http>,
+    commands: impl IntoIterator<Item = &'a crate::Command<U, E>>,
+    guild_id: Option<serenity::GuildId>,
+) -> Result<CommandSyncReport, serenity::Error> {
+    let http = http.as_ref();
+    let registered = match guild_id {
+        Some(guild_id) => guild_id.get_commands_with_localizations(http).await?,
+        None => serenity::Command::get_global_commands_with_localizations(http).await?,
+    };
+    // Registered commands are taken out of here as they are matched with local commands. The
+    // remaining ones are deleted
+    let mut registered = {
+        let mut canonicalized = Vec::with_capacity(registered.len());
+        for command in registered {
+            let canonical = canonicalize_command(&serenity::json::to_value(&command)?);
+            canonicalized.push(Some((command, canonical)));
+        }
+        canonicalized
+    };
+
+    let mut report = CommandSyncReport::default();
+    for builder in create_application_commands(commands) {
+        let local = canonicalize_command(&serenity::json::to_value(&builder)?);
+        let existing = registered
+            .iter_mut()
+            .find(|x| {
+                x.as_ref().is_some_and(|(_, remote)| {
+                    remote["name"] == local["name"] && remote["type"] == local["type"]
+                })
+            })
+            .and_then(Option::take);
+
+        match existing {
+            Some((command, remote)) if remote == local => report.unchanged.push(command.name),
+            Some((command, _)) => {
+                match guild_id {
+                    Some(guild_id) => guild_id.edit_command(http, command.id, builder).await?,
+                    None => {
+                        serenity::Command::edit_global_command(http, command.id, builder).await?
+                    }
+                };
+                report.edited.push(command.name);
+            }
+            None => {
+                let command = match guild_id {
+                    Some(guild_id) => guild_id.create_command(http, builder).await?,
+                    None => serenity::Command::create_global_command(http, builder).await?,
+                };
+                report.created.push(command.name);
+            }
+        }
+    }
+
+    for (command, _) in registered.into_iter().flatten() {
+        match guild_id {
+            Some(guild_id) => guild_id.delete_command(http, command.id).await?,
+            None => serenity::Command::delete_global_command(http, command.id).await?,
+        }
+        report.deleted.push(command.name);
+    }
+
+    Ok(report)
+}
+
+/// Reduces a serialized command, either a local [`serenity::CreateCommand`] or a registered
+/// [`serenity::Command`], to the fields that can be configured, filling in Discord's defaults for
+/// missing fields. That way, the two can be compared for equality
+fn canonicalize_command(command: &serenity::json::Value) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    let permissions = command.get("default_member_permissions");
+    json!({
+        "type": command.get("type").and_then(|x| x.as_u64()).unwrap_or(1),
+        "name": string_field(command, "name"),
+        "name_localizations": localizations_field(command, "name_localizations"),
+        "description": string_field(command, "description"),
+        "description_localizations": localizations_field(command, "description_localizations"),
+        "options": canonicalize_options(command.get("options")),
+        "default_member_permissions": permissions.and_then(|x| x.as_str()),
+        "dm_permission": bool_field(command, "dm_permission", true),
+        "nsfw": bool_field(command, "nsfw", false),
+    })
+}
+
+/// See [`canonicalize_command`]
+fn canonicalize_options(options: Option<&serenity::json::Value>) -> Vec<serenity::json::Value> {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    let canonicalize_choice = |choice: &serenity::json::Value| {
+        let value = choice.get("value").cloned().unwrap_or(NULL);
+        json!({
+            "name": string_field(choice, "name"),
+            "name_localizations": localizations_field(choice, "name_localizations"),
+            // Discord may return integers for float choices and vice versa
+            "value": value.as_f64().map_or(value, |x| json!(x)),
+        })
+    };
+    let canonicalize_option = |option: &serenity::json::Value| {
+        let choices = match option.get("choices").and_then(|x| x.as_array()) {
+            Some(choices) => choices.iter().map(canonicalize_choice).collect(),
+            None => Vec::new(),
+        };
+        let channel_types = match option.get("channel_types") {
+            Some(x) if !x.is_null() => x.clone(),
+            _ => json!([]),
+        };
+        json!({
+            "type": option.get("type").and_then(|x| x.as_u64()),
+            "name": string_field(option, "name"),
+            "name_localizations": localizations_field(option, "name_localizations"),
+            "description": string_field(option, "description"),
+            "description_localizations": localizations_field(option, "description_localizations"),
+            "required": bool_field(option, "required", false),
+            "autocomplete": bool_field(option, "autocomplete", false),
+            "choices": choices,
+            "channel_types": channel_types,
+            "min_value": option.get("min_value").and_then(|x| x.as_f64()),
+            "max_value": option.get("max_value").and_then(|x| x.as_f64()),
+            "min_length": option.get("min_length").and_then(|x| x.as_u64()),
+            "max_length": option.get("max_length").and_then(|x| x.as_u64()),
+            "options": canonicalize_options(option.get("options")),
+        })
+    };
+
+    match options.and_then(|x| x.as_array()) {
+        Some(options) => options.iter().map(canonicalize_option).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// See [`canonicalize_command`]. Missing strings are empty
+fn string_field<'a>(value: &'a serenity::json::Value, key: &str) -> &'a str {
+    value.get(key).and_then(|x| x.as_str()).unwrap_or("")
+}
+
+/// See [`canonicalize_command`]
+fn bool_field(value: &serenity::json::Value, key: &str, default: bool) -> bool {
+    value.get(key).and_then(|x| x.as_bool()).unwrap_or(default)
+}
+
+/// See [`canonicalize_command`]. Missing localizations are an empty map
+fn localizations_field(value: &serenity::json::Value, key: &str) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    match value.get(key) {
+        Some(x) if !x.is_null() => x.clone(),
+        _ => json!({}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize_command() {
+        #[allow(unused_imports)] // required for simd-json
+        use ::serenity::json::*;
+
+        let local = serenity::CreateCommand::new("ping")
+            .description("Pong")
+            .name_localized("de", "ping")
+            .add_option(
+                serenity::CreateCommandOption::new(
+                    serenity::CommandOptionType::Number,
+                    "amount",
+                    "How much",
+                )
+                .add_number_choice("one", 1.0)
+                .min_number_value(0.0),
+            );
+        let remote = json!({
+            "id": "1",
+            "application_id": "2",
+            "version": "3",
+            "type": 1,
+            "name": "ping",
+            "name_localizations": { "de": "ping" },
+            "description": "Pong",
+            "description_localizations": null,
+            "options": [{
+                "type": 10,
+                "name": "amount",
+                "description": "How much",
+                "choices": [{ "name": "one", "value": 1 }],
+                "min_value": 0,
+            }],
+            "default_member_permissions": null,
+            "nsfw": false,
+        });
+
+        let local = canonicalize_command(&to_value(local).unwrap());
+        assert_eq!(local, canonicalize_command(&remote));
+
+        let mut remote = remote;
+        remote["options"][0]["required"] = json!(true);
+        assert_ne!(local, canonicalize_command(&remote));
+    }
+}
@@ -0,0 +1,346 @@
+//! Composable command checks which can explain why they denied access
+
+use crate::BoxFuture;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Reason why a [`Check`] denied access to a command. Passed to the error handler in
+/// [`crate::FrameworkError::CommandCheckFailed`]
+///
+/// ```rust
+/// let denial = poise::CheckDenial::new("missing_role")
+///     .payload("role", "Moderator")
+///     .message("Only moderators can use this command");
+/// ```
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct CheckDenial {
+    /// Machine-readable reason, e.g. `"missing_role"`. Can be used as a translation key
+    pub key: String,
+    /// Details about the reason, e.g. the name of the missing role. Can be used as translation
+    /// arguments
+    pub payload: HashMap<String, String>,
+    /// Human-readable explanation, shown to the user by [`crate::builtins::on_error`]
+    pub message: Option<String>,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl CheckDenial {
+    /// Creates a denial with the given machine-readable reason
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a detail about the reason to [`Self::payload`]
+    pub fn payload(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.payload.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the human-readable explanation
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// Result of a successfully run [`Check`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CheckOutcome {
+    /// The command may run
+    Allowed,
+    /// The command may not run, optionally with a reason
+    Denied(Option<CheckDenial>),
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+impl CheckOutcome {
+    /// Shorthand for [`Self::Denied`] with a reason
+    pub fn denied(denial: CheckDenial) -> Self {
+        Self::Denied(Some(denial))
+    }
+}
+
+impl From<bool> for CheckOutcome {
+    fn from(allowed: bool) -> Self {
+        match allowed {
+            true => Self::Allowed,
+            false => Self::Denied(None),
+        }
+    }
+}
+
+/// Type of the function wrapped by [`Check`]
+type CheckFn<U, E> = dyn for<'a> Fn(crate::Context<'a, U, E>) -> BoxFuture<'a, Result<CheckOutcome, E>>
+    + Send
+    + Sync;
+
+/// A command check which can explain why it denied access, and can be combined with other checks
+/// using [`Self::all`], [`Self::any`] and [`Self::not`]. Set via
+/// [`crate::Command::structured_checks`] or the `structured_check` command attribute.
+///
+/// ```rust
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # type Context<'a> = poise::Context<'a, (), Error>;
+/// use poise::{Check, CheckDenial, CheckOutcome};
+///
+/// async fn is_admin(ctx: Context<'_>) -> Result<bool, Error> {
+///     Ok(ctx.author().id == 1234)
+/// }
+///
+/// fn moderator_or_admin() -> Check<(), Error> {
+///     let is_moderator = Check::new(|ctx| Box::pin(async move {
+///         if ctx.author().name.starts_with("mod_") {
+///             return Ok(CheckOutcome::Allowed);
+///         }
+///         let denial = CheckDenial::new("not_moderator").message("You're not a moderator");
+///         Ok(CheckOutcome::denied(denial))
+///     }));
+///     Check::any(vec![is_moderator, Check::from_bool(|ctx| Box::pin(is_admin(ctx)))])
+/// }
+///
+/// #[poise::command(prefix_command, structured_check = "moderator_or_admin")]
+/// async fn ban(ctx: Context<'_>) -> Result<(), Error> {
+///     Ok(())
+/// }
+/// ```
+#[derive(derivative::Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct Check<U, E> {
+    /// The check function
+    #[derivative(Debug = "ignore")]
+    check: Arc<CheckFn<U, E>>,
+}
+
+impl<U, E> Check<U, E> {
+    /// Wraps a check function
+    pub fn new(
+        check: impl for<'a> Fn(crate::Context<'a, U, E>) -> BoxFuture<'a, Result<CheckOutcome, E>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            check: Arc::new(check),
+        }
+    }
+
+    /// Runs the check
+    pub async fn run(&self, ctx: crate::Context<'_, U, E>) -> Result<CheckOutcome, E> {
+        (self.check)(ctx).await
+    }
+}
+
+impl<U: Send + Sync + 'static, E: 'static> Check<U, E> {
+    /// Wraps a plain check function as used in [`crate::Command::checks`]. Returning false denies
+    /// access without a reason
+    pub fn from_bool(
+        check: fn(crate::Context<'_, U, E>) -> BoxFuture<'_, Result<bool, E>>,
+    ) -> Self {
+        Self::new(move |ctx| Box::pin(async move { check(ctx).await.map(CheckOutcome::from) }))
+    }
+
+    /// Allows access if all given checks do. Checks run in order, and the first denial is
+    /// returned
+    pub fn all(checks: Vec<Self>) -> Self {
+        let checks = Arc::new(checks);
+        Self::new(move |ctx| {
+            let checks = checks.clone();
+            Box::pin(async move {
+                for check in &*checks {
+                    if let CheckOutcome::Denied(denial) = check.run(ctx).await? {
+                        return Ok(CheckOutcome::Denied(denial));
+                    }
+                }
+                Ok(CheckOutcome::Allowed)
+            })
+        })
+    }
+
+    /// Allows access if any of the given checks does. Checks run in order until one allows
+    /// access. If none does, the first denial is returned
+    pub fn any(checks: Vec<Self>) -> Self {
+        let checks = Arc::new(checks);
+        Self::new(move |ctx| {
+            let checks = checks.clone();
+            Box::pin(async move {
+                let mut first_denial = None;
+                for check in &*checks {
+                    match check.run(ctx).await? {
+                        CheckOutcome::Allowed => return Ok(CheckOutcome::Allowed),
+                        CheckOutcome::Denied(denial) => {
+                            first_denial = first_denial.or(Some(denial));
+                        }
+                        CheckOutcome::__NonExhaustive => unreachable!(),
+                    }
+                }
+                Ok(CheckOutcome::Denied(first_denial.flatten()))
+            })
+        })
+    }
+
+    /// Allows access if the given check denies it, and denies access with the given reason if the
+    /// check allows it
+    pub fn not(check: Self, denial: CheckDenial) -> Self {
+        Self::new(move |ctx| {
+            let check = check.clone();
+            let denial = denial.clone();
+            Box::pin(async move {
+                Ok(match check.run(ctx).await? {
+                    CheckOutcome::Allowed => CheckOutcome::denied(denial),
+                    CheckOutcome::Denied(_) => CheckOutcome::Allowed,
+                    CheckOutcome::__NonExhaustive => unreachable!(),
+                })
+            })
+        })
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    /// Records which checks ran and how the invocation ended, in order
+    #[derive(Default)]
+    struct Data {
+        events: Mutex<Vec<String>>,
+    }
+
+    fn check(name: &'static str, outcome: CheckOutcome) -> Check<Data, Error> {
+        Check::new(move |ctx: crate::Context<'_, Data, Error>| {
+            let outcome = outcome.clone();
+            Box::pin(async move {
+                ctx.data().events.lock().unwrap().push(name.to_owned());
+                Ok(outcome)
+            })
+        })
+    }
+
+    fn allow(name: &'static str) -> Check<Data, Error> {
+        check(name, CheckOutcome::Allowed)
+    }
+
+    fn deny(name: &'static str) -> Check<Data, Error> {
+        check(name, CheckOutcome::denied(CheckDenial::new(name)))
+    }
+
+    fn command(
+        name: &str,
+        structured_checks: Vec<Check<Data, Error>>,
+    ) -> crate::Command<Data, Error> {
+        crate::Command {
+            name: name.to_owned(),
+            prefix_action: Some(|ctx| {
+                Box::pin(async move {
+                    let events = &ctx.framework.user_data.events;
+                    events.lock().unwrap().push("ran".into());
+                    Ok(())
+                })
+            }),
+            structured_checks,
+            ..Default::default()
+        }
+    }
+
+    /// Dispatches the given message and returns the names of the checks which ran, followed by
+    /// "ran" or the key of the denial
+    async fn run(commands: Vec<crate::Command<Data, Error>>, content: &str) -> Vec<String> {
+        let options = crate::FrameworkOptions {
+            commands,
+            prefix_options: crate::PrefixFrameworkOptions {
+                prefix: Some("~".into()),
+                ..Default::default()
+            },
+            on_error: |error| {
+                Box::pin(async move {
+                    if let crate::FrameworkError::CommandCheckFailed { denial, ctx, .. } = error {
+                        let key = denial.map_or("none".into(), |denial| denial.key);
+                        ctx.data()
+                            .events
+                            .lock()
+                            .unwrap()
+                            .push(format!("denied: {key}"));
+                    }
+                })
+            },
+            ..Default::default()
+        };
+        let harness = crate::testing::TestHarness::new(options, Data::default())
+            .await
+            .unwrap();
+        harness.dispatch_message(harness.message(content)).await;
+        let events = harness.framework().user_data.events.lock().unwrap().clone();
+        events
+    }
+
+    #[tokio::test]
+    async fn test_all() {
+        let checks = Check::all(vec![allow("a"), deny("b"), deny("c")]);
+        let events = run(vec![command("test", vec![checks])], "~test").await;
+        assert_eq!(events, ["a", "b", "denied: b"]);
+
+        let checks = Check::all(vec![allow("a"), allow("b")]);
+        let events = run(vec![command("test", vec![checks])], "~test").await;
+        assert_eq!(events, ["a", "b", "ran"]);
+    }
+
+    #[tokio::test]
+    async fn test_any() {
+        let checks = Check::any(vec![deny("a"), allow("b"), deny("c")]);
+        let events = run(vec![command("test", vec![checks])], "~test").await;
+        assert_eq!(events, ["a", "b", "ran"]);
+
+        let checks = Check::any(vec![deny("a"), deny("b")]);
+        let events = run(vec![command("test", vec![checks])], "~test").await;
+        assert_eq!(events, ["a", "b", "denied: a"]);
+
+        // The first denial wins even if it has no reason
+        let checks = Check::any(vec![check("a", CheckOutcome::Denied(None)), deny("b")]);
+        let events = run(vec![command("test", vec![checks])], "~test").await;
+        assert_eq!(events, ["a", "b", "denied: none"]);
+    }
+
+    #[tokio::test]
+    async fn test_not() {
+        let checks = Check::not(deny("a"), CheckDenial::new("not"));
+        let events = run(vec![command("test", vec![checks])], "~test").await;
+        assert_eq!(events, ["a", "ran"]);
+
+        let checks = Check::not(allow("a"), CheckDenial::new("not"));
+        let events = run(vec![command("test", vec![checks])], "~test").await;
+        assert_eq!(events, ["a", "denied: not"]);
+
+        let checks = Check::not(
+            Check::any(vec![deny("a"), deny("b")]),
+            CheckDenial::new("not"),
+        );
+        let events = run(vec![command("test", vec![checks])], "~test").await;
+        assert_eq!(events, ["a", "b", "ran"]);
+    }
+
+    #[tokio::test]
+    async fn test_checks_of_parent_commands() {
+        // A command's checks run in order, after those of its parents
+        let mut parent = command("parent", vec![allow("parent")]);
+        parent.subcommands = vec![command("child", vec![allow("child 1"), allow("child 2")])];
+        let events = run(vec![parent], "~parent child").await;
+        assert_eq!(events, ["parent", "child 1", "child 2", "ran"]);
+
+        let mut parent = command("parent", vec![deny("parent")]);
+        parent.subcommands = vec![command("child", vec![allow("child")])];
+        let events = run(vec![parent], "~parent child").await;
+        assert_eq!(events, ["parent", "denied: parent"]);
+
+        let mut parent = command("parent", vec![allow("parent")]);
+        parent.subcommands = vec![command("child", vec![deny("child 1"), deny("child 2")])];
+        let events = run(vec![parent], "~parent child").await;
+        assert_eq!(events, ["parent", "child 1", "denied: child 1"]);
+    }
+}
@@ -0,0 +1,255 @@
+//! Infrastructure for limiting how many invocations of a command may run at the same time
+
+use crate::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Which invocations share a concurrency limit. See [`MaxConcurrency::scope`]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ConcurrencyScope {
+    /// All invocations of the command share the limit
+    #[default]
+    Global,
+    /// Each user has their own limit
+    User,
+    /// Each guild has its own limit. In DMs, each channel has its own limit
+    Guild,
+    /// Each channel has its own limit
+    Channel,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// Limits how many invocations of a command may run at the same time, set via
+/// [`crate::Command::max_concurrency`].
+///
+/// Unlike cooldowns, which space out the start times of invocations, this limits how many are in
+/// progress at once, e.g. to keep a single user from running many heavy commands in parallel.
+///
+/// ```rust
+/// let max_concurrency = poise::MaxConcurrency::new(1, poise::ConcurrencyScope::User)
+///     .queue(true)
+///     .queue_timeout(std::time::Duration::from_secs(60));
+/// ```
+#[derive(Default, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct MaxConcurrency {
+    /// How many invocations may run at once within the scope. Treated as one if zero
+    pub limit: u32,
+    /// Which invocations share the limit
+    pub scope: ConcurrencyScope,
+    /// If true, invocations exceeding the limit wait until a running one finishes. If false,
+    /// they're rejected with [`crate::FrameworkError::ConcurrencyLimit`]
+    pub queue: bool,
+    /// How long queued invocations wait at most before they're rejected with
+    /// [`crate::FrameworkError::ConcurrencyLimit`]. If None, [`crate::Command::timeout`] or
+    /// [`crate::FrameworkOptions::command_timeout`] is used, and if neither is set either,
+    /// invocations wait indefinitely
+    pub queue_timeout: Option<Duration>,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl MaxConcurrency {
+    /// Creates a limit which rejects invocations exceeding it
+    pub fn new(limit: u32, scope: ConcurrencyScope) -> Self {
+        Self {
+            limit,
+            scope,
+            ..Default::default()
+        }
+    }
+
+    /// Sets [`Self::queue`]
+    pub fn queue(mut self, queue: bool) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// Sets [`Self::queue_timeout`]
+    pub fn queue_timeout(mut self, queue_timeout: Duration) -> Self {
+        self.queue_timeout = Some(queue_timeout);
+        self
+    }
+
+    /// Identifies the slot shared by invocations in the given context
+    fn bucket(
+        &self,
+        user_id: serenity::UserId,
+        guild_id: Option<serenity::GuildId>,
+        channel_id: serenity::ChannelId,
+    ) -> u64 {
+        // IDs are snowflakes, so guild and channel IDs never collide
+        match self.scope {
+            ConcurrencyScope::Global => 0,
+            ConcurrencyScope::User => user_id.get(),
+            ConcurrencyScope::Guild => guild_id.map_or(channel_id.get(), |id| id.get()),
+            ConcurrencyScope::Channel => channel_id.get(),
+            ConcurrencyScope::__NonExhaustive => unreachable!(),
+        }
+    }
+}
+
+/// Semaphores of all buckets of a command which currently have running or queued invocations
+type Semaphores = Arc<Mutex<HashMap<u64, Arc<tokio::sync::Semaphore>>>>;
+
+/// Tracks the running invocations of a single command, see [`crate::Command::concurrency`]
+#[derive(Default, Debug)]
+pub struct ConcurrencyTracker {
+    /// Buckets are removed once their last invocation finishes
+    semaphores: Semaphores,
+}
+
+impl ConcurrencyTracker {
+    /// Creates a tracker without running invocations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves a slot for an invocation in the given context. The slot is freed when the returned
+    /// permit is dropped.
+    ///
+    /// If the limit is reached, waits for a free slot if [`MaxConcurrency::queue`] is set, and
+    /// returns None otherwise. [`MaxConcurrency::queue_timeout`] isn't applied here, wrap the
+    /// returned future in [`tokio::time::timeout`] for that.
+    pub async fn acquire(
+        &self,
+        config: &MaxConcurrency,
+        user_id: serenity::UserId,
+        guild_id: Option<serenity::GuildId>,
+        channel_id: serenity::ChannelId,
+    ) -> Option<ConcurrencyPermit> {
+        let bucket = config.bucket(user_id, guild_id, channel_id);
+        let permit = if config.queue {
+            // The semaphore is never closed
+            self.semaphore(config, bucket).acquire_owned().await.ok()
+        } else {
+            self.semaphore(config, bucket).try_acquire_owned().ok()
+        };
+        self.permit(permit, bucket)
+    }
+
+    /// Like [`Self::acquire`], but returns None right away if the limit is reached, even if
+    /// [`MaxConcurrency::queue`] is set
+    pub fn try_acquire(
+        &self,
+        config: &MaxConcurrency,
+        user_id: serenity::UserId,
+        guild_id: Option<serenity::GuildId>,
+        channel_id: serenity::ChannelId,
+    ) -> Option<ConcurrencyPermit> {
+        let bucket = config.bucket(user_id, guild_id, channel_id);
+        let permit = self.semaphore(config, bucket).try_acquire_owned().ok();
+        self.permit(permit, bucket)
+    }
+
+    /// Returns the semaphore of the given bucket, creating it if needed
+    fn semaphore(&self, config: &MaxConcurrency, bucket: u64) -> Arc<tokio::sync::Semaphore> {
+        self.semaphores
+            .lock()
+            .unwrap()
+            .entry(bucket)
+            .or_insert_with(|| {
+                let limit = config.limit.max(1) as usize;
+                Arc::new(tokio::sync::Semaphore::new(limit))
+            })
+            .clone()
+    }
+
+    /// Wraps an acquired semaphore permit, if any
+    fn permit(
+        &self,
+        permit: Option<tokio::sync::OwnedSemaphorePermit>,
+        bucket: u64,
+    ) -> Option<ConcurrencyPermit> {
+        let permit = ConcurrencyPermit {
+            permit,
+            bucket,
+            semaphores: self.semaphores.clone(),
+        };
+        permit.permit.is_some().then_some(permit)
+    }
+}
+
+/// A reserved slot of a [`ConcurrencyTracker`], which is freed when dropped
+///
+/// Returned by [`crate::acquire_concurrency_permit`], which is why it may also be empty.
+#[derive(Default, Debug)]
+pub struct ConcurrencyPermit {
+    /// None for empty permits
+    permit: Option<tokio::sync::OwnedSemaphorePermit>,
+    /// Which bucket the slot belongs to
+    bucket: u64,
+    /// Where to clean up the bucket when it's no longer used
+    semaphores: Semaphores,
+}
+
+impl ConcurrencyPermit {
+    /// Creates a permit which doesn't reserve anything, for commands without
+    /// [`crate::Command::max_concurrency`]
+    pub fn empty() -> Self {
+        Self::default()
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        // Release the slot before checking whether anyone else still uses the bucket
+        let semaphore = match self.permit.take() {
+            Some(permit) => permit.semaphore().clone(),
+            None => return,
+        };
+
+        let mut semaphores = self.semaphores.lock().unwrap();
+        // Acquiring clones the semaphore while holding the lock, so if the map and this function
+        // hold the only references, no invocation is running or queued in this bucket anymore
+        if Arc::strong_count(&semaphore) == 2 {
+            semaphores.remove(&self.bucket);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reject_and_cleanup() {
+        let tracker = ConcurrencyTracker::new();
+        let config = MaxConcurrency::new(1, ConcurrencyScope::User);
+        let (user, other_user) = (serenity::UserId::new(1), serenity::UserId::new(2));
+        let channel_id = serenity::ChannelId::new(3);
+        let acquire = |user_id| tracker.acquire(&config, user_id, None, channel_id);
+
+        let permit = acquire(user).await;
+        assert!(permit.is_some());
+        assert!(acquire(user).await.is_none());
+        assert!(acquire(other_user).await.is_some());
+
+        drop(permit);
+        assert!(tracker.semaphores.lock().unwrap().is_empty());
+        assert!(acquire(user).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_queue() {
+        let tracker = ConcurrencyTracker::new();
+        let config = MaxConcurrency::new(1, ConcurrencyScope::Global).queue(true);
+        let (user_id, channel_id) = (serenity::UserId::new(1), serenity::ChannelId::new(2));
+
+        let permit = tracker.try_acquire(&config, user_id, None, channel_id);
+        assert!(permit.is_some());
+        assert!(tracker
+            .try_acquire(&config, user_id, None, channel_id)
+            .is_none());
+
+        let queued = tracker.acquire(&config, user_id, None, channel_id);
+        let timeout = Duration::from_millis(10);
+        assert!(tokio::time::timeout(timeout, queued).await.is_err());
+
+        let queued = tracker.acquire(&config, user_id, None, channel_id);
+        drop(permit);
+        assert!(queued.await.is_some());
+        assert!(tracker.semaphores.lock().unwrap().is_empty());
+    }
+}
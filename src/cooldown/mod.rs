@@ -0,0 +1,754 @@
+//! Infrastructure for command cooldowns
+
+use crate::serenity_prelude as serenity;
+// I usually don't really do imports, but these are very convenient
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+mod store;
+pub use store::*;
+
+/// Subset of [`crate::Context`] so that [`Cooldowns`] can be used without requiring a full [Context](`crate::Context`)
+/// (ie from within an `event_handler`)
+#[derive(Default, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct CooldownContext {
+    /// The user associated with this request
+    pub user_id: serenity::UserId,
+    /// The guild this request originated from or `None`
+    pub guild_id: Option<serenity::GuildId>,
+    /// The channel associated with this request
+    pub channel_id: serenity::ChannelId,
+    /// Roles of the user in the guild, or empty if not in a guild. Used for
+    /// [`CooldownConfig::exempt_roles`] and [`CooldownConfig::role_overrides`]
+    pub roles: Vec<serenity::RoleId>,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl CooldownContext {
+    /// Creates a context for the given user, guild and channel, without any roles
+    pub fn new(
+        user_id: serenity::UserId,
+        guild_id: Option<serenity::GuildId>,
+        channel_id: serenity::ChannelId,
+    ) -> Self {
+        Self {
+            user_id,
+            guild_id,
+            channel_id,
+            roles: Vec::new(),
+            __non_exhaustive: (),
+        }
+    }
+}
+
+/// How a cooldown spreads its allowed uses over its duration. See [`CooldownConfig::strategy`]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum CooldownStrategy {
+    /// At most `uses` invocations may happen within any time span of the cooldown duration. Once
+    /// exhausted, the next use becomes available when the oldest invocation in the window expires.
+    #[default]
+    SlidingWindow,
+    /// The bucket holds up to `uses` tokens, one of which is consumed per invocation. Tokens are
+    /// refilled one by one, evenly spread over the cooldown duration.
+    TokenBucket,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// When the framework's automatic cooldown handling counts an invocation towards the cooldown. See
+/// [`CooldownConfig::policy`]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum CooldownPolicy {
+    /// The cooldown starts when the command runs, regardless of the command's outcome. Invocations
+    /// whose arguments fail to parse or don't match the registered slash command don't count
+    #[default]
+    Always,
+    /// The cooldown only starts once the command has returned successfully
+    OnSuccess,
+    /// The cooldown starts when the command runs, but is refunded if the command returns an
+    /// error or panics
+    RefundOnError,
+    /// The cooldown starts when the command runs, but is refunded if the command panics.
+    ///
+    /// Panics are only caught with the `handle_panics` feature enabled.
+    RefundOnPanic,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// Configuration struct for [`Cooldowns`]
+///
+/// Each bucket allows one invocation per configured duration by default. Set the corresponding
+/// `_uses` field to allow multiple invocations per duration, e.g. 5 uses per 60 seconds per user.
+#[derive(Default, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct CooldownConfig {
+    /// This cooldown operates on a global basis
+    pub global: Option<Duration>,
+    /// This cooldown operates on a per-user basis
+    pub user: Option<Duration>,
+    /// This cooldown operates on a per-guild basis
+    pub guild: Option<Duration>,
+    /// This cooldown operates on a per-channel basis
+    pub channel: Option<Duration>,
+    /// This cooldown operates on a per-member basis
+    pub member: Option<Duration>,
+    /// Number of invocations allowed per [`Self::global`] duration. One if `None`
+    pub global_uses: Option<u32>,
+    /// Number of invocations allowed per [`Self::user`] duration. One if `None`
+    pub user_uses: Option<u32>,
+    /// Number of invocations allowed per [`Self::guild`] duration. One if `None`
+    pub guild_uses: Option<u32>,
+    /// Number of invocations allowed per [`Self::channel`] duration. One if `None`
+    pub channel_uses: Option<u32>,
+    /// Number of invocations allowed per [`Self::member`] duration. One if `None`
+    pub member_uses: Option<u32>,
+    /// How multiple uses are spread over the cooldown durations. Irrelevant for single-use
+    /// cooldowns, where both strategies behave the same
+    pub strategy: CooldownStrategy,
+    /// Members with any of these roles are not subject to this cooldown at all
+    pub exempt_roles: Vec<serenity::RoleId>,
+    /// Members with all of these permissions in the channel are not subject to this cooldown at
+    /// all. Ignored if empty.
+    ///
+    /// Only checked by the framework's automatic cooldown handling, because it requires looking
+    /// up permissions.
+    pub exempt_permissions: serenity::Permissions,
+    /// Scales the cooldown durations for members with certain roles. If multiple apply, the
+    /// shortest cooldown wins
+    pub role_overrides: Vec<CooldownRoleOverride>,
+    /// Which command outcomes count towards the cooldown. Only used by the framework's automatic
+    /// cooldown handling; with [`crate::FrameworkOptions::manual_cooldowns`], use
+    /// [`CooldownTracker::refund_cooldown`] yourself
+    pub policy: CooldownPolicy,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+/// Scales all cooldown durations for members with a certain role. See
+/// [`CooldownConfig::role_overrides`]
+///
+/// ```rust
+/// # use poise::serenity_prelude as serenity;
+/// # let booster_role = serenity::RoleId::new(1);
+/// let mut config = poise::CooldownConfig::default();
+/// // Boosters only have to wait half as long
+/// config.role_overrides.push(poise::CooldownRoleOverride::new(booster_role, 50));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct CooldownRoleOverride {
+    /// The role this override applies to
+    pub role_id: serenity::RoleId,
+    /// Cooldown durations are scaled to this percentage, e.g. 50 for half the cooldown. Zero
+    /// disables the cooldown entirely
+    pub duration_percent: u32,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl CooldownRoleOverride {
+    /// Scales cooldown durations for members with the given role to the given percentage
+    pub fn new(role_id: serenity::RoleId, duration_percent: u32) -> Self {
+        Self {
+            role_id,
+            duration_percent,
+            __non_exhaustive: (),
+        }
+    }
+}
+
+/// Identifies a single cooldown bucket of a command, e.g. the per-user cooldown of one specific user
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum CooldownBucket {
+    /// See [`CooldownConfig::global`]
+    Global,
+    /// See [`CooldownConfig::user`]
+    User(serenity::UserId),
+    /// See [`CooldownConfig::guild`]
+    Guild(serenity::GuildId),
+    /// See [`CooldownConfig::channel`]
+    Channel(serenity::ChannelId),
+    /// See [`CooldownConfig::member`]
+    Member(serenity::UserId, serenity::GuildId),
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// A single configured cooldown bucket: `uses` invocations per `duration`
+#[derive(Clone, Copy)]
+struct RateLimit {
+    /// Number of invocations allowed per duration; at least one
+    uses: u32,
+    /// See [`CooldownConfig::global`] and friends
+    duration: Duration,
+    /// See [`CooldownConfig::strategy`]
+    strategy: CooldownStrategy,
+}
+
+impl RateLimit {
+    /// Returns None if the bucket is not configured
+    fn new(
+        duration: Option<Duration>,
+        uses: Option<u32>,
+        strategy: CooldownStrategy,
+    ) -> Option<Self> {
+        Some(Self {
+            uses: uses.unwrap_or(1).max(1),
+            duration: duration?,
+            strategy,
+        })
+    }
+
+    /// Time it takes for a single token to refill in [`CooldownStrategy::TokenBucket`]
+    fn refill_interval(self) -> Duration {
+        self.duration / self.uses
+    }
+}
+
+impl CooldownConfig {
+    /// Collects all configured buckets that apply in the given context
+    fn buckets(&self, ctx: &CooldownContext) -> Vec<(CooldownBucket, RateLimit)> {
+        if self
+            .exempt_roles
+            .iter()
+            .any(|role| ctx.roles.contains(role))
+        {
+            return Vec::new();
+        }
+        let duration_percent = self
+            .role_overrides
+            .iter()
+            .filter(|o| ctx.roles.contains(&o.role_id))
+            .map(|o| o.duration_percent)
+            .min();
+
+        let strategy = self.strategy;
+        let mut buckets = vec![
+            (
+                CooldownBucket::Global,
+                RateLimit::new(self.global, self.global_uses, strategy),
+            ),
+            (
+                CooldownBucket::User(ctx.user_id),
+                RateLimit::new(self.user, self.user_uses, strategy),
+            ),
+            (
+                CooldownBucket::Channel(ctx.channel_id),
+                RateLimit::new(self.channel, self.channel_uses, strategy),
+            ),
+        ];
+
+        if let Some(guild_id) = ctx.guild_id {
+            buckets.push((
+                CooldownBucket::Guild(guild_id),
+                RateLimit::new(self.guild, self.guild_uses, strategy),
+            ));
+            buckets.push((
+                CooldownBucket::Member(ctx.user_id, guild_id),
+                RateLimit::new(self.member, self.member_uses, strategy),
+            ));
+        }
+
+        buckets
+            .into_iter()
+            .filter_map(|(bucket, limit)| {
+                let mut limit = limit?;
+                if let Some(percent) = duration_percent {
+                    let scaled = limit.duration.checked_mul(percent).map(|d| d / 100);
+                    limit.duration = scaled.unwrap_or(limit.duration);
+                }
+                Some((bucket, limit)).filter(|_| !limit.duration.is_zero())
+            })
+            .collect()
+    }
+}
+
+/// Time that passed between two points in time; zero if the clock went backwards
+fn elapsed(earlier: SystemTime, later: SystemTime) -> Duration {
+    later.duration_since(earlier).unwrap_or_default()
+}
+
+/// Usage history of a single cooldown bucket, e.g. the invocations of one user.
+///
+/// Timestamps are wall clock times, so that the state stays meaningful when persisted by a
+/// [`CooldownStore`] across restarts.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct CooldownBucketState {
+    /// Most recent invocations, oldest first. Used by [`CooldownStrategy::SlidingWindow`]
+    pub invocations: Vec<SystemTime>,
+    /// Point in time at which the token bucket will be completely refilled. Used by
+    /// [`CooldownStrategy::TokenBucket`]
+    pub full_at: Option<SystemTime>,
+    /// Point in time after which this state no longer affects any cooldown and can be discarded.
+    /// See [`Self::is_expired`]
+    pub expires_at: Option<SystemTime>,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl CooldownBucketState {
+    /// Whether the bucket's cooldown has fully run out at the given point in time, i.e. the state
+    /// is indistinguishable from a never used bucket and can be removed from storage
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.map_or(true, |expires_at| expires_at <= now)
+    }
+
+    /// Number of invocations that are allowed at the given point in time
+    fn available_uses(&self, limit: RateLimit, at: SystemTime) -> u32 {
+        let used = match limit.strategy {
+            CooldownStrategy::TokenBucket => {
+                let backlog = self
+                    .full_at
+                    .map_or(Duration::ZERO, |full_at| elapsed(at, full_at));
+                let interval = limit.refill_interval().as_nanos().max(1);
+                // Rounding up: a partially refilled token is not available yet
+                backlog.as_nanos().div_ceil(interval)
+            }
+            _ => self
+                .invocations
+                .iter()
+                .filter(|&&invocation| elapsed(invocation, at) < limit.duration)
+                .count() as u128,
+        };
+        limit
+            .uses
+            .saturating_sub(std::convert::TryFrom::try_from(used).unwrap_or(u32::MAX))
+    }
+
+    /// Time until the next invocation is allowed, or None if it's allowed right now
+    fn remaining_cooldown(&self, limit: RateLimit, now: SystemTime) -> Option<Duration> {
+        let remaining = match limit.strategy {
+            CooldownStrategy::TokenBucket => {
+                // The bucket must have been refilled by at least one token
+                let tolerance = limit.duration - limit.refill_interval();
+                elapsed(now, self.full_at?).checked_sub(tolerance)?
+            }
+            _ => {
+                let elapsed = self
+                    .invocations
+                    .iter()
+                    .map(|&invocation| elapsed(invocation, now))
+                    .filter(|&elapsed| elapsed < limit.duration)
+                    .collect::<Vec<_>>();
+                // The oldest invocation that keeps the window full must expire
+                let blocking = elapsed.len().checked_sub(limit.uses as usize)?;
+                limit.duration - elapsed[blocking]
+            }
+        };
+        Some(remaining).filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Records an invocation
+    fn record(&mut self, limit: RateLimit, now: SystemTime) {
+        self.invocations
+            .retain(|&invocation| elapsed(invocation, now) < limit.duration);
+        self.invocations.push(now);
+        let excess = self.invocations.len().saturating_sub(limit.uses as usize);
+        self.invocations.drain(..excess);
+
+        let backlog_start = self.full_at.map_or(now, |full_at| full_at.max(now));
+        let full_at = backlog_start + limit.refill_interval();
+        self.full_at = Some(full_at);
+        self.expires_at = Some(full_at.max(now + limit.duration));
+    }
+
+    /// Undoes the [`Self::record`] of the invocation recorded at the given point in time. The
+    /// expiry is left as is, which at worst keeps the state around a bit longer than needed
+    fn refund(&mut self, limit: RateLimit, recorded_at: SystemTime) {
+        if let Some(i) = self.invocations.iter().rposition(|&i| i == recorded_at) {
+            self.invocations.remove(i);
+        }
+        // Tokens are interchangeable, but one that has been refilled already can't be refunded
+        if self.full_at.is_some_and(|full_at| full_at > recorded_at) {
+            self.full_at = self
+                .full_at
+                .and_then(|full_at| full_at.checked_sub(limit.refill_interval()));
+        }
+    }
+}
+
+/// Computes the cooldown result from a list of buckets. See [`CooldownTracker::remaining_cooldown`]
+fn remaining_cooldown<'a>(
+    buckets: impl Iterator<Item = (RateLimit, Option<&'a CooldownBucketState>)>,
+    now: SystemTime,
+) -> Option<Duration> {
+    buckets
+        .filter_map(|(limit, state)| state?.remaining_cooldown(limit, now))
+        .max()
+}
+
+/// Computes the remaining uses from a list of buckets. See [`CooldownTracker::remaining_uses_at`]
+fn remaining_uses<'a>(
+    buckets: impl Iterator<Item = (RateLimit, Option<&'a CooldownBucketState>)>,
+    at: SystemTime,
+) -> Option<u32> {
+    buckets
+        .map(|(limit, state)| state.map_or(limit.uses, |s| s.available_uses(limit, at)))
+        .min()
+}
+
+/// Tracks all types of cooldowns for a single command
+///
+/// The framework's automatic cooldown handling doesn't use this, but stores cooldowns in
+/// [`crate::FrameworkOptions::cooldown_store`]. This type is meant for implementing custom
+/// cooldown behavior with [`crate::FrameworkOptions::manual_cooldowns`].
+///
+/// Expired entries are removed as the tracker grows. To bound memory usage even under heavy load,
+/// set a hard cap with [`Self::with_max_entries_per_bucket`].
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct CooldownTracker {
+    /// Stores the invocation history per bucket type and bucket
+    buckets: HashMap<BucketType, BucketStates>,
+    /// See [`Self::with_max_entries_per_bucket`]
+    max_entries_per_bucket: Option<usize>,
+    /// See [`Self::with_config`]
+    config: Option<CooldownConfig>,
+}
+
+/// Distinguishes global, user, guild, channel and member buckets
+type BucketType = std::mem::Discriminant<CooldownBucket>;
+
+/// Usage history of all buckets of one type, e.g. of all users
+type BucketStates = HashMap<CooldownBucket, CooldownBucketState>;
+
+/// Inserts a bucket state, making room first if `max_entries` is reached: expired entries are
+/// removed first, then the entries closest to expiring
+fn insert_capped(
+    states: &mut BucketStates,
+    bucket: CooldownBucket,
+    state: CooldownBucketState,
+    max_entries: Option<usize>,
+    now: SystemTime,
+) {
+    if let Some(max_entries) = max_entries {
+        if states.len() >= max_entries && !states.contains_key(&bucket) {
+            states.retain(|_, state| !state.is_expired(now));
+        }
+        if states.len() >= max_entries && !states.contains_key(&bucket) {
+            // Evict a tenth at once so that this doesn't happen on every single insert
+            let target_len = max_entries - 1 - max_entries / 10;
+            let mut expiries = states
+                .iter()
+                .map(|(bucket, state)| (state.expires_at, *bucket))
+                .collect::<Vec<_>>();
+            expiries.sort_unstable_by_key(|(expires_at, _)| *expires_at);
+            for (_, bucket) in &expiries[..states.len() - target_len] {
+                states.remove(bucket);
+            }
+        }
+    }
+    states.insert(bucket, state);
+}
+
+/// **Renamed to [`CooldownTracker`]**
+pub use CooldownTracker as Cooldowns;
+
+impl CooldownTracker {
+    /// Create a new cooldown tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a tracker which keeps at most the given number of entries per bucket type, like
+    /// [`InMemoryCooldownStore::with_max_entries_per_bucket`]
+    pub fn with_max_entries_per_bucket(max_entries: usize) -> Self {
+        Self {
+            max_entries_per_bucket: Some(max_entries.max(1)),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the config assumed by the deprecated [`Self::start_cooldown`], to know when its
+    /// records expire. Without one, they're kept until the tracker runs out of room
+    pub fn with_config(mut self, config: CooldownConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Returns the usage history of the given bucket, if any
+    fn get(&self, bucket: &CooldownBucket) -> Option<&CooldownBucketState> {
+        self.buckets
+            .get(&std::mem::discriminant(bucket))?
+            .get(bucket)
+    }
+
+    /// Queries the cooldown buckets and checks if all cooldowns have expired and command
+    /// execution may proceed. If not, Some is returned with the remaining cooldown
+    pub fn remaining_cooldown(
+        &self,
+        ctx: CooldownContext,
+        cooldown_durations: &CooldownConfig,
+    ) -> Option<Duration> {
+        let buckets = cooldown_durations.buckets(&ctx).into_iter();
+        let buckets = buckets.map(|(bucket, limit)| (limit, self.get(&bucket)));
+        remaining_cooldown(buckets, SystemTime::now())
+    }
+
+    /// Returns how many more times the command may be invoked right now before a cooldown is hit,
+    /// as limited by the most restrictive bucket. None if no cooldown applies in this context
+    pub fn remaining_uses(&self, ctx: CooldownContext, config: &CooldownConfig) -> Option<u32> {
+        self.remaining_uses_at(ctx, config, SystemTime::now())
+    }
+
+    /// Like [`Self::remaining_uses`], but at the given point in time, assuming no invocations
+    /// happen in between.
+    ///
+    /// Used with [`Self::remaining_cooldown`] to tell how many uses will be available after waiting
+    /// out the cooldown.
+    pub fn remaining_uses_at(
+        &self,
+        ctx: CooldownContext,
+        config: &CooldownConfig,
+        at: SystemTime,
+    ) -> Option<u32> {
+        let buckets = config.buckets(&ctx).into_iter();
+        let buckets = buckets.map(|(bucket, limit)| (limit, self.get(&bucket)));
+        remaining_uses(buckets, at)
+    }
+
+    /// Indicates that a command has been executed and all associated cooldowns should start running
+    ///
+    /// Only records the most recent invocation per bucket, so it doesn't support
+    /// [`CooldownConfig::strategy`] or the `_uses` fields. The records expire as configured by
+    /// [`Self::with_config`].
+    #[deprecated = "use start_cooldown_with_config, which supports all cooldown settings"]
+    pub fn start_cooldown(&mut self, ctx: CooldownContext) {
+        /// How long records are kept if the tracker has no config
+        const UNKNOWN_DURATION: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+        let now = SystemTime::now();
+        let durations = self.config.as_ref().map(|config| config.buckets(&ctx));
+        let mut buckets = vec![
+            CooldownBucket::Global,
+            CooldownBucket::User(ctx.user_id),
+            CooldownBucket::Channel(ctx.channel_id),
+        ];
+        if let Some(guild_id) = ctx.guild_id {
+            buckets.push(CooldownBucket::Guild(guild_id));
+            buckets.push(CooldownBucket::Member(ctx.user_id, guild_id));
+        }
+        for bucket in buckets {
+            let states = self
+                .buckets
+                .entry(std::mem::discriminant(&bucket))
+                .or_default();
+            let duration = match &durations {
+                Some(durations) => durations
+                    .iter()
+                    .find(|(configured, _)| *configured == bucket)
+                    .map_or(Duration::ZERO, |(_, limit)| limit.duration),
+                None => UNKNOWN_DURATION,
+            };
+            let state = CooldownBucketState {
+                invocations: vec![now],
+                expires_at: Some(now + duration),
+                ..Default::default()
+            };
+            insert_capped(states, bucket, state, self.max_entries_per_bucket, now);
+        }
+    }
+
+    /// Indicates that a command has been executed and all associated cooldowns should start running
+    ///
+    /// The config is needed to know how much invocation history must be kept for each bucket.
+    /// Returns when the invocation was recorded, to pass to [`Self::refund_cooldown`].
+    pub fn start_cooldown_with_config(
+        &mut self,
+        ctx: CooldownContext,
+        config: &CooldownConfig,
+    ) -> SystemTime {
+        let now = SystemTime::now();
+        for (bucket, limit) in config.buckets(&ctx) {
+            let states = self
+                .buckets
+                .entry(std::mem::discriminant(&bucket))
+                .or_default();
+            let mut state = states.remove(&bucket).unwrap_or_default();
+            state.record(limit, now);
+
+            // Unlike the cooldown store, nothing purges the tracker periodically, so do it
+            // whenever the map would have to grow
+            if states.len() == states.capacity() {
+                states.retain(|_, state| !state.is_expired(now));
+                // Grow anyway if little was purged, so that this doesn't happen on every insert
+                if states.len() > states.capacity() / 2 {
+                    states.reserve(states.len());
+                }
+            }
+            insert_capped(states, bucket, state, self.max_entries_per_bucket, now);
+        }
+        now
+    }
+
+    /// Undoes a [`Self::start_cooldown_with_config`] in all buckets which apply in the given
+    /// context, e.g. because the command failed. `recorded_at` is the value it returned
+    pub fn refund_cooldown(
+        &mut self,
+        ctx: CooldownContext,
+        config: &CooldownConfig,
+        recorded_at: SystemTime,
+    ) {
+        for (bucket, limit) in config.buckets(&ctx) {
+            let states = self.buckets.get_mut(&std::mem::discriminant(&bucket));
+            if let Some(state) = states.and_then(|states| states.get_mut(&bucket)) {
+                state.refund(limit, recorded_at);
+            }
+        }
+    }
+
+    /// Removes the history of all buckets whose cooldown has run out, to free memory
+    pub fn purge_expired(&mut self) {
+        let now = SystemTime::now();
+        for states in self.buckets.values_mut() {
+            states.retain(|_, state| !state.is_expired(now));
+        }
+        self.buckets.retain(|_, states| !states.is_empty());
+    }
+}
+
+impl<'a> From<&'a serenity::Message> for CooldownContext {
+    fn from(message: &'a serenity::Message) -> Self {
+        Self {
+            user_id: message.author.id,
+            channel_id: message.channel_id,
+            guild_id: message.guild_id,
+            roles: match &message.member {
+                Some(member) => member.roles.clone(),
+                None => Vec::new(),
+            },
+            __non_exhaustive: (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_uses(strategy: CooldownStrategy, expected_waits: &[u64]) {
+        let limit = RateLimit {
+            uses: 3,
+            duration: Duration::from_secs(30),
+            strategy,
+        };
+        let start = SystemTime::now();
+        let mut state = CooldownBucketState::default();
+
+        for _ in 0..3 {
+            assert_eq!(state.remaining_cooldown(limit, start), None);
+            state.record(limit, start);
+        }
+        assert_eq!(state.available_uses(limit, start), 0);
+
+        for (i, &wait) in expected_waits.iter().enumerate() {
+            let at = start + Duration::from_secs(i as u64 * 5);
+            assert_eq!(
+                state.remaining_cooldown(limit, at),
+                Some(Duration::from_secs(wait))
+            );
+        }
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        // All three uses expire at once, 30 seconds after the burst
+        check_uses(CooldownStrategy::SlidingWindow, &[30, 25, 20]);
+
+        let limit = RateLimit::new(Some(Duration::from_secs(30)), Some(3), Default::default());
+        let mut state = CooldownBucketState::default();
+        let start = SystemTime::now();
+        state.record(limit.unwrap(), start);
+        let after = start + Duration::from_secs(30);
+        assert_eq!(state.available_uses(limit.unwrap(), after), 3);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        // One token is refilled every 10 seconds
+        check_uses(CooldownStrategy::TokenBucket, &[10, 5]);
+
+        let limit = RateLimit::new(
+            Some(Duration::from_secs(30)),
+            Some(3),
+            CooldownStrategy::TokenBucket,
+        );
+        let mut state = CooldownBucketState::default();
+        let start = SystemTime::now();
+        for _ in 0..3 {
+            state.record(limit.unwrap(), start);
+        }
+        let after = start + Duration::from_secs(20);
+        assert_eq!(state.available_uses(limit.unwrap(), after), 2);
+    }
+
+    #[test]
+    fn test_refund() {
+        for strategy in [
+            CooldownStrategy::SlidingWindow,
+            CooldownStrategy::TokenBucket,
+        ] {
+            let limit = RateLimit::new(Some(Duration::from_secs(30)), Some(2), strategy).unwrap();
+            let mut state = CooldownBucketState::default();
+            let start = SystemTime::now();
+            let second = start + Duration::from_secs(1);
+            state.record(limit, start);
+            state.record(limit, second);
+            assert_eq!(state.available_uses(limit, second), 0);
+
+            // The first invocation is refunded after the second one was recorded
+            state.refund(limit, start);
+            assert_eq!(state.available_uses(limit, second), 1);
+            assert_eq!(state.remaining_cooldown(limit, second), None);
+            if strategy == CooldownStrategy::SlidingWindow {
+                assert_eq!(state.invocations, [second]);
+            }
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_start_cooldown_without_config() {
+        let config = CooldownConfig {
+            user: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let mut tracker = CooldownTracker::new();
+        tracker.start_cooldown(CooldownContext::default());
+        tracker.purge_expired();
+        assert!(tracker
+            .remaining_cooldown(CooldownContext::default(), &config)
+            .is_some());
+
+        // With a config, records expire after the configured duration
+        let mut tracker = CooldownTracker::new().with_config(config.clone());
+        tracker.start_cooldown(CooldownContext::default());
+        tracker.purge_expired();
+        assert!(tracker
+            .remaining_cooldown(CooldownContext::default(), &config)
+            .is_some());
+        // Only the user bucket is configured, so the others were purged
+        assert_eq!(tracker.buckets.len(), 1);
+    }
+
+    #[test]
+    fn test_tracker_max_entries_per_bucket() {
+        let config = CooldownConfig {
+            user: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let mut tracker = CooldownTracker::with_max_entries_per_bucket(10);
+        for user_id in 1..=25 {
+            let ctx = CooldownContext {
+                user_id: serenity::UserId::new(user_id),
+                ..Default::default()
+            };
+            tracker.start_cooldown_with_config(ctx, &config);
+        }
+
+        let user_bucket = CooldownBucket::User(serenity::UserId::new(25));
+        let users = &tracker.buckets[&std::mem::discriminant(&user_bucket)];
+        assert!(users.len() <= 10);
+        // The most recent invocation is never evicted
+        assert!(users.contains_key(&user_bucket));
+    }
+}
@@ -0,0 +1,317 @@
+//! Pluggable storage for the framework's cooldown state
+
+use super::{
+    BucketStates, BucketType, CooldownBucket, CooldownBucketState, CooldownConfig, CooldownContext,
+};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Identifies a single cooldown bucket of a single command in a [`CooldownStore`]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct CooldownKey {
+    /// The command's [`crate::Command::cooldown_key`], which is its
+    /// [`crate::Command::identifying_name`], or its [`crate::Command::cooldown_group`] prefixed
+    /// with `group:` if set
+    pub command: String,
+    /// Which bucket of the command
+    pub bucket: CooldownBucket,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl CooldownKey {
+    /// Creates a key for the given bucket of the command with the given identifying name
+    pub fn new(command: impl Into<String>, bucket: CooldownBucket) -> Self {
+        Self {
+            command: command.into(),
+            bucket,
+            __non_exhaustive: (),
+        }
+    }
+}
+
+/// Storage backend for the framework's automatic cooldown handling, set via
+/// [`crate::FrameworkOptions::cooldown_store`].
+///
+/// The default is [`InMemoryCooldownStore`], which forgets all cooldowns on restart. To keep
+/// cooldowns across restarts, either implement this trait on top of your database, or
+/// [`Self::snapshot`] the in-memory store on shutdown and [`Self::restore`] it on startup.
+///
+/// Only [`Self::get`], [`Self::set`], [`Self::snapshot`] and [`Self::restore`] need to be
+/// implemented. The checking and recording logic is provided on top of those, though you may
+/// override it, e.g. to do it atomically in your database.
+///
+/// Note that the default [`Self::start_cooldown`] reads and writes each bucket state separately, so
+/// two invocations starting at the exact same time may overwrite each other's record.
+#[async_trait::async_trait]
+pub trait CooldownStore: Send + Sync {
+    /// Returns the usage history of the given bucket, or None if it was never used
+    async fn get(&self, key: &CooldownKey) -> Option<CooldownBucketState>;
+
+    /// Replaces the usage history of the given bucket
+    async fn set(&self, key: CooldownKey, state: CooldownBucketState);
+
+    /// Returns the usage history of all buckets, e.g. to persist them on shutdown
+    async fn snapshot(&self) -> Vec<(CooldownKey, CooldownBucketState)>;
+
+    /// Replaces the usage history of all buckets with the given one, e.g. one loaded on startup
+    async fn restore(&self, buckets: Vec<(CooldownKey, CooldownBucketState)>);
+
+    /// Checks all buckets of the given command which apply in the given context.
+    ///
+    /// If the command may not be invoked right now, returns the time until it may be invoked
+    /// again, and how many uses will be available at that point.
+    async fn remaining_cooldown(
+        &self,
+        command: &str,
+        ctx: &CooldownContext,
+        config: &CooldownConfig,
+    ) -> Option<(Duration, u32)> {
+        let mut buckets = Vec::new();
+        for (bucket, limit) in config.buckets(ctx) {
+            buckets.push((limit, self.get(&CooldownKey::new(command, bucket)).await));
+        }
+        let buckets = || {
+            buckets
+                .iter()
+                .map(|(limit, state)| (*limit, state.as_ref()))
+        };
+
+        let now = SystemTime::now();
+        let remaining_cooldown = super::remaining_cooldown(buckets(), now)?;
+        let remaining_uses = super::remaining_uses(buckets(), now + remaining_cooldown);
+        Some((remaining_cooldown, remaining_uses.unwrap_or(1)))
+    }
+
+    /// Removes the usage history of all buckets whose cooldown has run out, see
+    /// [`CooldownBucketState::is_expired`]. Called periodically by [`crate::Framework`].
+    ///
+    /// Does nothing by default; stores which don't override this keep expired entries until they
+    /// are overwritten.
+    async fn purge_expired(&self) {}
+
+    /// Records an invocation of the given command in all its buckets which apply in the given
+    /// context. Returns when the invocation was recorded, to pass to [`Self::refund_cooldown`]
+    async fn start_cooldown(
+        &self,
+        command: &str,
+        ctx: &CooldownContext,
+        config: &CooldownConfig,
+    ) -> SystemTime {
+        let now = SystemTime::now();
+        for (bucket, limit) in config.buckets(ctx) {
+            let key = CooldownKey::new(command, bucket);
+            let mut state = self.get(&key).await.unwrap_or_default();
+            state.record(limit, now);
+            self.set(key, state).await;
+        }
+        now
+    }
+
+    /// Undoes a [`Self::start_cooldown`] of the given command in all its buckets which apply in
+    /// the given context. `recorded_at` is the value it returned. Used for
+    /// [`crate::CooldownPolicy::RefundOnError`] and [`crate::CooldownPolicy::RefundOnPanic`], and
+    /// when argument parsing fails or the slash command structure doesn't match
+    async fn refund_cooldown(
+        &self,
+        command: &str,
+        ctx: &CooldownContext,
+        config: &CooldownConfig,
+        recorded_at: SystemTime,
+    ) {
+        for (bucket, limit) in config.buckets(ctx) {
+            let key = CooldownKey::new(command, bucket);
+            if let Some(mut state) = self.get(&key).await {
+                state.refund(limit, recorded_at);
+                self.set(key, state).await;
+            }
+        }
+    }
+}
+
+/// The default [`CooldownStore`], which keeps all cooldowns in memory
+///
+/// Expired entries are purged periodically by [`crate::Framework`]. To bound memory usage even
+/// under heavy load, set a hard cap with [`Self::with_max_entries_per_bucket`].
+///
+/// ```rust
+/// # use poise::CooldownStore as _;
+/// # #[tokio::main(flavor = "current_thread")] async fn main() {
+/// let store = poise::InMemoryCooldownStore::new();
+/// let config = poise::CooldownConfig {
+///     user: Some(std::time::Duration::from_secs(60)),
+///     ..Default::default()
+/// };
+/// store.start_cooldown("ping", &Default::default(), &config).await;
+///
+/// // E.g. persist this on shutdown and load it into the new store on startup
+/// let snapshot = store.snapshot().await;
+/// let restored = poise::InMemoryCooldownStore::new();
+/// restored.restore(snapshot).await;
+/// assert!(restored.remaining_cooldown("ping", &Default::default(), &config).await.is_some());
+/// # }
+/// ```
+#[derive(Default, Debug)]
+pub struct InMemoryCooldownStore {
+    /// Usage history per command, bucket type and bucket
+    commands: std::sync::Mutex<HashMap<String, HashMap<BucketType, BucketStates>>>,
+    /// See [`Self::with_max_entries_per_bucket`]
+    max_entries_per_bucket: Option<usize>,
+}
+
+impl InMemoryCooldownStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty store which keeps at most the given number of entries per command and
+    /// bucket type, e.g. at most 100,000 users for the per-user cooldown of a command.
+    ///
+    /// When full, expired entries are removed first, then the entries closest to expiring. The
+    /// cooldowns of those are reset early.
+    pub fn with_max_entries_per_bucket(max_entries: usize) -> Self {
+        Self {
+            commands: Default::default(),
+            max_entries_per_bucket: Some(max_entries.max(1)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CooldownStore for InMemoryCooldownStore {
+    async fn get(&self, key: &CooldownKey) -> Option<CooldownBucketState> {
+        let commands = self.commands.lock().unwrap();
+        let states = commands
+            .get(&key.command)?
+            .get(&std::mem::discriminant(&key.bucket))?;
+        states.get(&key.bucket).cloned()
+    }
+
+    async fn set(&self, key: CooldownKey, state: CooldownBucketState) {
+        let mut commands = self.commands.lock().unwrap();
+        let bucket_type = std::mem::discriminant(&key.bucket);
+        let states = commands
+            .entry(key.command)
+            .or_default()
+            .entry(bucket_type)
+            .or_default();
+        let now = SystemTime::now();
+        super::insert_capped(states, key.bucket, state, self.max_entries_per_bucket, now);
+    }
+
+    async fn snapshot(&self) -> Vec<(CooldownKey, CooldownBucketState)> {
+        let commands = self.commands.lock().unwrap();
+        let mut snapshot = Vec::new();
+        for (command, bucket_types) in &*commands {
+            for (bucket, state) in bucket_types.values().flatten() {
+                let key = CooldownKey::new(command.clone(), *bucket);
+                snapshot.push((key, state.clone()));
+            }
+        }
+        snapshot
+    }
+
+    async fn restore(&self, buckets: Vec<(CooldownKey, CooldownBucketState)>) {
+        let mut commands = self.commands.lock().unwrap();
+        commands.clear();
+        for (key, state) in buckets {
+            let bucket_type = std::mem::discriminant(&key.bucket);
+            let states = commands.entry(key.command).or_default();
+            states
+                .entry(bucket_type)
+                .or_default()
+                .insert(key.bucket, state);
+        }
+    }
+
+    async fn purge_expired(&self) {
+        let now = SystemTime::now();
+        let mut commands = self.commands.lock().unwrap();
+        for bucket_types in commands.values_mut() {
+            for states in bucket_types.values_mut() {
+                states.retain(|_, state| !state.is_expired(now));
+            }
+            bucket_types.retain(|_, states| !states.is_empty());
+        }
+        commands.retain(|_, bucket_types| !bucket_types.is_empty());
+    }
+
+    async fn start_cooldown(
+        &self,
+        command: &str,
+        ctx: &CooldownContext,
+        config: &CooldownConfig,
+    ) -> SystemTime {
+        // Overridden to record atomically
+        let now = SystemTime::now();
+        let mut commands = self.commands.lock().unwrap();
+        let bucket_types = commands.entry(command.to_owned()).or_default();
+        for (bucket, limit) in config.buckets(ctx) {
+            let states = bucket_types
+                .entry(std::mem::discriminant(&bucket))
+                .or_default();
+            let mut state = states.remove(&bucket).unwrap_or_default();
+            state.record(limit, now);
+            super::insert_capped(states, bucket, state, self.max_entries_per_bucket, now);
+        }
+        now
+    }
+
+    async fn refund_cooldown(
+        &self,
+        command: &str,
+        ctx: &CooldownContext,
+        config: &CooldownConfig,
+        recorded_at: SystemTime,
+    ) {
+        // Overridden to refund atomically
+        let mut commands = self.commands.lock().unwrap();
+        let Some(bucket_types) = commands.get_mut(command) else {
+            return;
+        };
+        for (bucket, limit) in config.buckets(ctx) {
+            let state = bucket_types
+                .get_mut(&std::mem::discriminant(&bucket))
+                .and_then(|states| states.get_mut(&bucket));
+            if let Some(state) = state {
+                state.refund(limit, recorded_at);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_max_entries_per_bucket() {
+        let store = InMemoryCooldownStore::with_max_entries_per_bucket(10);
+        let config = CooldownConfig {
+            user: Some(Duration::from_secs(60)),
+            channel: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        for user_id in 1..=25 {
+            let ctx = CooldownContext {
+                user_id: crate::serenity_prelude::UserId::new(user_id),
+                ..Default::default()
+            };
+            store.start_cooldown("ping", &ctx, &config).await;
+        }
+
+        let snapshot = store.snapshot().await;
+        let users = snapshot
+            .iter()
+            .filter(|(key, _)| matches!(key.bucket, CooldownBucket::User(_)))
+            .count();
+        assert!(users <= 10);
+        // The single channel bucket is unaffected by the user bucket's cap
+        assert_eq!(snapshot.len(), users + 1);
+
+        // The most recent invocation is never evicted
+        let latest = CooldownBucket::User(crate::serenity_prelude::UserId::new(25));
+        assert!(snapshot.iter().any(|(key, _)| key.bucket == latest));
+    }
+}
@@ -2,23 +2,50 @@
 
 use crate::serenity_prelude as serenity;
 
-/// Retrieves user permissions in the given channel. If unknown, returns None. If in DMs, returns
-/// `Permissions::all()`.
-async fn user_permissions(
-    ctx: &serenity::Context,
-    guild_id: Option<serenity::GuildId>,
-    channel_id: serenity::ChannelId,
+/// Resolves the permissions of the given user in the channel of the invocation. If unknown,
+/// returns None. If in DMs, returns `Permissions::all()`.
+///
+/// For slash commands, the permissions that Discord includes in the interaction are used for the
+/// invoking user and the bot. Otherwise, permissions are computed from the guild roles, the member
+/// and the channel, each taken from the cache or fetched via HTTP. In threads, the permission
+/// overwrites of the parent channel apply.
+pub async fn resolve_permissions<U, E>(
+    ctx: crate::Context<'_, U, E>,
     user_id: serenity::UserId,
 ) -> Option<serenity::Permissions> {
-    let guild_id = match guild_id {
+    let guild_id = match ctx.guild_id() {
         Some(x) => x,
         None => return Some(serenity::Permissions::all()), // no permission checks in DMs
     };
 
-    let guild = guild_id.to_partial_guild(ctx).await.ok()?;
+    if let crate::Context::Application(ctx) = ctx {
+        let interaction = ctx.interaction;
+        let resolved = if user_id == interaction.user.id {
+            interaction
+                .member
+                .as_ref()
+                .and_then(|member| member.permissions)
+        } else if user_id == ctx.framework.bot_id {
+            interaction.app_permissions
+        } else {
+            None
+        };
+        if let Some(permissions) = resolved {
+            return Some(permissions);
+        }
+    }
+
+    let serenity_context = ctx.serenity_context();
+    let guild = match guild_id.to_partial_guild(serenity_context).await {
+        Ok(guild) => guild,
+        Err(e) => {
+            tracing::warn!("Error when getting guild for permission check: {}", e);
+            return None;
+        }
+    };
 
     // Use to_channel so that it can fallback on HTTP for threads (which aren't in cache usually)
-    let channel = match channel_id.to_channel(ctx).await {
+    let mut channel = match ctx.channel_id().to_channel(serenity_context).await {
         Ok(serenity::Channel::Guild(channel)) => channel,
         Ok(_other_channel) => {
             tracing::warn!(
@@ -26,10 +53,39 @@ async fn user_permissions(
             );
             return None;
         }
-        Err(_) => return None,
+        Err(e) => {
+            tracing::warn!("Error when getting channel for permission check: {}", e);
+            return None;
+        }
     };
+    // Threads have no permission overwrites of their own
+    if let Some(parent_id) = channel.parent_id.filter(|_| is_thread(&channel)) {
+        channel = match parent_id.to_channel(serenity_context).await {
+            Ok(serenity::Channel::Guild(parent)) => parent,
+            Ok(_other_channel) => {
+                tracing::warn!(
+                    "thread parent is supposedly a non-guild channel. Denying invocation"
+                );
+                return None;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Error when getting thread parent for permission check: {}",
+                    e
+                );
+                return None;
+            }
+        };
+    }
 
-    let member = guild.member(ctx, user_id).await.ok()?;
+    // Checks the cache first and falls back to HTTP
+    let member = match guild.member(serenity_context, user_id).await {
+        Ok(member) => member,
+        Err(e) => {
+            tracing::warn!("Error when getting member for permission check: {}", e);
+            return None;
+        }
+    };
 
     Some(guild.user_permissions_in(&channel, &member))
 }
@@ -46,26 +102,267 @@ async fn missing_permissions<U, E>(
         return Some(serenity::Permissions::empty());
     }
 
-    let permissions = user_permissions(
-        ctx.serenity_context(),
-        ctx.guild_id(),
-        ctx.channel_id(),
-        user,
-    )
-    .await;
+    let permissions = resolve_permissions(ctx, user).await;
     Some(required_permissions - permissions?)
 }
 
-/// See [`check_permissions_and_cooldown`]. Runs the check only for a single command. The caller
-/// should call this multiple time for each parent command to achieve the check inheritance logic.
-async fn check_permissions_and_cooldown_single<'a, U, E>(
+/// Whether the channel is a thread, including forum posts
+fn is_thread(channel: &serenity::GuildChannel) -> bool {
+    use serenity::ChannelType;
+
+    matches!(
+        channel.kind,
+        ChannelType::NewsThread | ChannelType::PublicThread | ChannelType::PrivateThread
+    )
+}
+
+/// Whether the channel is of the given kind. `parent` is the parent channel if the channel is a
+/// thread
+fn is_channel_kind(
+    kind: crate::ChannelKind,
+    channel: &serenity::Channel,
+    parent: Option<&serenity::GuildChannel>,
+) -> bool {
+    use serenity::ChannelType;
+
+    let channel = match (kind, channel) {
+        (crate::ChannelKind::Dm, serenity::Channel::Private(_)) => return true,
+        (_, serenity::Channel::Guild(channel)) => channel,
+        _ => return false,
+    };
+    let is_thread = is_thread(channel);
+    match kind {
+        crate::ChannelKind::Text => channel.kind == ChannelType::Text,
+        crate::ChannelKind::News => channel.kind == ChannelType::News,
+        crate::ChannelKind::Voice => channel.kind == ChannelType::Voice,
+        crate::ChannelKind::Stage => channel.kind == ChannelType::Stage,
+        crate::ChannelKind::Thread => is_thread,
+        crate::ChannelKind::ForumPost => {
+            is_thread && parent.is_some_and(|parent| parent.kind == ChannelType::Forum)
+        }
+        crate::ChannelKind::Dm | crate::ChannelKind::__NonExhaustive => false,
+    }
+}
+
+/// Checks [`crate::Command::channel_kinds`] and
+/// [`crate::FrameworkOptions::dynamic_channel_restrictions`]
+async fn check_channel<'a, U, E>(
     ctx: crate::Context<'a, U, E>,
     cmd: &'a crate::Command<U, E>,
 ) -> Result<(), crate::FrameworkError<'a, U, E>> {
-    // Skip command checks if `FrameworkOptions::skip_checks_for_owners` is set to true
-    if ctx.framework().options.skip_checks_for_owners
-        && ctx.framework().options().owners.contains(&ctx.author().id)
+    let restrictions = match ctx.framework().options().dynamic_channel_restrictions {
+        Some(dynamic_channel_restrictions) => {
+            match dynamic_channel_restrictions(ctx.into(), cmd).await {
+                Ok(restrictions) => restrictions,
+                Err(error) => {
+                    return Err(crate::FrameworkError::CommandCheckFailed {
+                        error: Some(error),
+                        denial: None,
+                        ctx,
+                    })
+                }
+            }
+        }
+        None => None,
+    };
+    if cmd.channel_kinds.is_empty() && restrictions.is_none() {
+        return Ok(());
+    }
+
+    let channel = match ctx.channel_id().to_channel(ctx.serenity_context()).await {
+        Ok(channel) => Some(channel),
+        Err(e) => {
+            tracing::warn!("Error when getting channel: {}", e);
+            None
+        }
+    };
+    // Threads are restricted like their parent channel, and forum posts are recognized by it
+    let parent_id = match &channel {
+        Some(serenity::Channel::Guild(channel)) if is_thread(channel) => channel.parent_id,
+        _ => None,
+    };
+    let parent = match parent_id {
+        Some(parent_id) => match parent_id.to_channel(ctx.serenity_context()).await {
+            Ok(serenity::Channel::Guild(parent)) => Some(parent),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("Error when getting parent channel: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    // When the channel is unknown, restrict access like in the nsfw_only check
+    if !cmd.channel_kinds.is_empty()
+        && !channel.as_ref().is_some_and(|channel| {
+            let is_kind = |&kind| is_channel_kind(kind, channel, parent.as_ref());
+            cmd.channel_kinds.iter().any(is_kind)
+        })
+    {
+        return Err(crate::FrameworkError::ChannelKindNotAllowed {
+            allowed_kinds: cmd.channel_kinds.clone(),
+            ctx,
+        });
+    }
+
+    if let Some(restrictions) = restrictions {
+        let channel = match &channel {
+            Some(x) => x,
+            None => return Err(crate::FrameworkError::ChannelNotAllowed { ctx }),
+        };
+        // The channel itself, its category or parent channel, and the parent channel's category
+        let lineage = [
+            Some(channel.id()),
+            match channel {
+                serenity::Channel::Guild(channel) => channel.parent_id,
+                _ => None,
+            },
+            parent.as_ref().and_then(|parent| parent.parent_id),
+        ];
+        let matches = |channel_id: &serenity::ChannelId| lineage.contains(&Some(*channel_id));
+        if restrictions.denied.iter().any(matches)
+            || (!restrictions.allowed.is_empty() && !restrictions.allowed.iter().any(matches))
+        {
+            return Err(crate::FrameworkError::ChannelNotAllowed { ctx });
+        }
+    }
+
+    Ok(())
+}
+
+/// Retrieves the roles of the invoking member, from the invocation itself, the cache or HTTP. If
+/// unknown, returns None. If in DMs, returns an empty list.
+async fn member_roles<U, E>(ctx: crate::Context<'_, U, E>) -> Option<Vec<serenity::RoleId>> {
+    let guild_id = match ctx.guild_id() {
+        Some(x) => x,
+        None => return Some(Vec::new()),
+    };
+
+    let roles = match ctx {
+        crate::Context::Application(ctx) => ctx.interaction.member.as_ref().map(|m| &m.roles),
+        crate::Context::Prefix(ctx) => ctx.msg.member.as_ref().map(|m| &m.roles),
+    };
+    if let Some(roles) = roles {
+        return Some(roles.clone());
+    }
+
+    // Checks the cache first and falls back to HTTP
+    match guild_id
+        .member(ctx.serenity_context(), ctx.author().id)
+        .await
     {
+        Ok(member) => Some(member.roles),
+        Err(e) => {
+            tracing::warn!("Error when getting member roles: {}", e);
+            None
+        }
+    }
+}
+
+/// Retrieves the IDs and names of all roles in the guild, from the cache or HTTP. If unknown or in
+/// DMs, returns None.
+async fn guild_role_names<U, E>(
+    ctx: crate::Context<'_, U, E>,
+) -> Option<Vec<(serenity::RoleId, String)>> {
+    let guild_id = ctx.guild_id()?;
+
+    #[cfg(feature = "cache")]
+    if let Some(guild) = ctx.cache().guild(guild_id) {
+        let roles = guild.roles.values();
+        return Some(roles.map(|role| (role.id, role.name.clone())).collect());
+    }
+
+    match guild_id.roles(ctx.http()).await {
+        Ok(roles) => Some(
+            roles
+                .into_values()
+                .map(|role| (role.id, role.name))
+                .collect(),
+        ),
+        Err(e) => {
+            tracing::warn!("Error when getting guild roles: {}", e);
+            None
+        }
+    }
+}
+
+/// Checks [`crate::Command::required_roles`], [`crate::Command::any_role`] and
+/// [`crate::Command::forbidden_roles`]
+async fn check_roles<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+    cmd: &'a crate::Command<U, E>,
+) -> Result<(), crate::FrameworkError<'a, U, E>> {
+    let all_roles = || {
+        cmd.required_roles
+            .iter()
+            .chain(&cmd.any_role)
+            .chain(&cmd.forbidden_roles)
+    };
+    if all_roles().next().is_none() {
+        return Ok(());
+    }
+
+    let mut member_roles = member_roles(ctx).await;
+    let mut role_names = Vec::new();
+    if all_roles().any(|role| matches!(role, crate::RoleIdentifier::Name(_))) {
+        match guild_role_names(ctx).await {
+            Some(names) => role_names = names,
+            // Better safe than sorry: if names can't be resolved, treat the roles as unknown
+            None if ctx.guild_id().is_some() => member_roles = None,
+            None => {}
+        }
+    }
+
+    let member_roles = match member_roles {
+        Some(x) => x,
+        // Better safe than sorry: when roles are unknown, restrict access
+        None => {
+            return Err(crate::FrameworkError::MissingRoles {
+                missing_roles: cmd.required_roles.clone(),
+                missing_any_role: cmd.any_role.clone(),
+                forbidden_roles: cmd.forbidden_roles.clone(),
+                ctx,
+            })
+        }
+    };
+    let has_role = |role: &&crate::RoleIdentifier| match role {
+        crate::RoleIdentifier::Id(role_id) => member_roles.contains(role_id),
+        crate::RoleIdentifier::Name(name) => role_names
+            .iter()
+            .any(|(role_id, role_name)| role_name == name && member_roles.contains(role_id)),
+        crate::RoleIdentifier::__NonExhaustive => unreachable!(),
+    };
+
+    let missing_roles = cmd.required_roles.iter().filter(|role| !has_role(role));
+    let missing_roles = missing_roles.cloned().collect::<Vec<_>>();
+    let missing_any_role = match cmd.any_role.iter().any(|role| has_role(&role)) {
+        true => Vec::new(),
+        false => cmd.any_role.clone(),
+    };
+    let forbidden_roles = cmd.forbidden_roles.iter().filter(has_role);
+    let forbidden_roles = forbidden_roles.cloned().collect::<Vec<_>>();
+
+    if missing_roles.is_empty() && missing_any_role.is_empty() && forbidden_roles.is_empty() {
+        Ok(())
+    } else {
+        Err(crate::FrameworkError::MissingRoles {
+            missing_roles,
+            missing_any_role,
+            forbidden_roles,
+            ctx,
+        })
+    }
+}
+
+/// See [`check_permissions_and_cooldown`]. Runs the checks only for a single command, except for
+/// its cooldown. The caller should call this multiple time for each parent command to achieve the
+/// check inheritance logic.
+async fn check_permissions_single<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+    cmd: &'a crate::Command<U, E>,
+) -> Result<(), crate::FrameworkError<'a, U, E>> {
+    if skips_checks(ctx) {
         return Ok(());
     }
 
@@ -108,6 +405,10 @@ async fn check_permissions_and_cooldown_single<'a, U, E>(
         }
     }
 
+    check_channel(ctx, cmd).await?;
+
+    check_roles(ctx, cmd).await?;
+
     // Make sure that user has required permissions
     match missing_permissions(ctx, ctx.author().id, cmd.required_permissions).await {
         Some(missing_permissions) if missing_permissions.is_empty() => {}
@@ -146,45 +447,423 @@ async fn check_permissions_and_cooldown_single<'a, U, E>(
         match check(ctx).await {
             Ok(true) => {}
             Ok(false) => {
-                return Err(crate::FrameworkError::CommandCheckFailed { ctx, error: None })
+                return Err(crate::FrameworkError::CommandCheckFailed {
+                    error: None,
+                    denial: None,
+                    ctx,
+                })
+            }
+            Err(error) => {
+                return Err(crate::FrameworkError::CommandCheckFailed {
+                    error: Some(error),
+                    denial: None,
+                    ctx,
+                })
+            }
+        }
+    }
+    for check in &cmd.structured_checks {
+        match check.run(ctx).await {
+            Ok(crate::CheckOutcome::Allowed) => {}
+            Ok(crate::CheckOutcome::Denied(denial)) => {
+                return Err(crate::FrameworkError::CommandCheckFailed {
+                    error: None,
+                    denial,
+                    ctx,
+                })
             }
+            Ok(crate::CheckOutcome::__NonExhaustive) => unreachable!(),
             Err(error) => {
                 return Err(crate::FrameworkError::CommandCheckFailed {
                     error: Some(error),
+                    denial: None,
                     ctx,
                 })
             }
         }
     }
 
-    if !ctx.framework().options().manual_cooldowns {
-        let cooldowns = cmd.cooldowns.lock().unwrap();
-        let config = cmd.cooldown_config.read().unwrap();
-        let remaining_cooldown = cooldowns.remaining_cooldown(ctx.cooldown_context(), &config);
-        if let Some(remaining_cooldown) = remaining_cooldown {
-            return Err(crate::FrameworkError::CooldownHit {
+    Ok(())
+}
+
+/// Whether the checks are skipped for the invoking user because of
+/// [`crate::FrameworkOptions::skip_checks_for_owners`]
+fn skips_checks<U, E>(ctx: crate::Context<'_, U, E>) -> bool {
+    let options = ctx.framework().options();
+    options.skip_checks_for_owners && options.owners.contains(&ctx.author().id)
+}
+
+/// Resolves the cooldown config of the given command, or returns None if the invocation doesn't
+/// count towards the cooldown. See [`check_cooldown`]
+async fn counted_cooldown_config<U, E>(
+    ctx: crate::Context<'_, U, E>,
+    cmd: &crate::Command<U, E>,
+) -> Result<Option<crate::CooldownConfig>, E> {
+    if ctx.framework().options().manual_cooldowns || skips_checks(ctx) {
+        return Ok(None);
+    }
+
+    let config = cmd.resolve_cooldown_config(ctx.into()).await?;
+    if !config.exempt_permissions.is_empty() {
+        let missing = missing_permissions(ctx, ctx.author().id, config.exempt_permissions);
+        if missing.await.is_some_and(|missing| missing.is_empty()) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(config))
+}
+
+/// Checks the cooldown of a single command in [`crate::FrameworkOptions::cooldown_store`].
+///
+/// Returns the resolved cooldown config if the invocation counts towards the cooldown, or None if
+/// it doesn't, because of [`crate::FrameworkOptions::manual_cooldowns`],
+/// [`crate::FrameworkOptions::skip_checks_for_owners`] or
+/// [`crate::CooldownConfig::exempt_permissions`].
+async fn check_cooldown<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+    cmd: &'a crate::Command<U, E>,
+) -> Result<Option<crate::CooldownConfig>, crate::FrameworkError<'a, U, E>> {
+    let config = match counted_cooldown_config(ctx, cmd).await {
+        Ok(Some(config)) => config,
+        Ok(None) => return Ok(None),
+        Err(error) => {
+            return Err(crate::FrameworkError::CommandCheckFailed {
+                error: Some(error),
+                denial: None,
                 ctx,
-                remaining_cooldown,
-            });
+            })
         }
+    };
+
+    let cooldown_group = cmd.cooldown_group.as_deref();
+    let remaining_cooldown = ctx
+        .framework()
+        .options()
+        .cooldown_store
+        .remaining_cooldown(&cmd.cooldown_key(), &ctx.cooldown_context(), &config)
+        .await;
+    if let Some((remaining_cooldown, remaining_uses)) = remaining_cooldown {
+        return Err(crate::FrameworkError::CooldownHit {
+            ctx,
+            remaining_cooldown,
+            remaining_uses,
+            cooldown_group,
+        });
     }
+    Ok(Some(config))
+}
 
-    Ok(())
+/// Checks whether the invoking user or guild is on [`crate::FrameworkOptions::blocklist`]. If so,
+/// responds with [`crate::FrameworkOptions::blocklist_message`] if set and `respond` is true
+pub async fn check_blocklist<U, E>(ctx: crate::Context<'_, U, E>, respond: bool) -> bool {
+    let options = ctx.framework().options();
+    if options.owners.contains(&ctx.author().id) {
+        return false;
+    }
+
+    let entry = match options
+        .blocklist
+        .blocked(ctx.author().id, ctx.guild_id())
+        .await
+    {
+        Some(x) => x,
+        None => return false,
+    };
+    if let (true, Some(blocklist_message)) = (respond, options.blocklist_message) {
+        let reply = crate::CreateReply::default()
+            .content(blocklist_message(&entry))
+            .ephemeral(true);
+        if let Err(e) = ctx.send(reply).await {
+            tracing::warn!("Error when sending blocklist message: {}", e);
+        }
+    }
+    true
+}
+
+/// See [`check_permissions_and_cooldown`]. Rejects commands disabled via
+/// [`crate::FrameworkOptions::registry`] and runs the checks of the command and all its parents,
+/// except for cooldowns
+#[allow(clippy::needless_lifetimes)] // false positive (clippy issue 7271)
+async fn check_permissions<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+) -> Result<(), crate::FrameworkError<'a, U, E>> {
+    let registry = &ctx.framework().options().registry;
+    let is_disabled = ctx
+        .parent_commands()
+        .iter()
+        .copied()
+        .chain(std::iter::once(ctx.command()))
+        .any(|command| !registry.is_enabled(&command.qualified_name, ctx.guild_id()));
+    if is_disabled {
+        return Err(crate::FrameworkError::CommandDisabled { ctx });
+    }
+
+    for parent_command in ctx.parent_commands() {
+        check_permissions_single(ctx, parent_command).await?;
+    }
+    check_permissions_single(ctx, ctx.command()).await
+}
+
+/// See [`check_permissions_and_cooldown`]. Checks the cooldowns of the command and all its parents.
+///
+/// Returns the command's cooldown config if the invocation counts towards its cooldown, see
+/// [`check_cooldown`].
+#[allow(clippy::needless_lifetimes)] // false positive (clippy issue 7271)
+async fn check_cooldowns<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+) -> Result<Option<crate::CooldownConfig>, crate::FrameworkError<'a, U, E>> {
+    for parent_command in ctx.parent_commands() {
+        check_cooldown(ctx, parent_command).await?;
+    }
+    check_cooldown(ctx, ctx.command()).await
+}
+
+/// See [`check_permissions_and_cooldown`]. Also returns the command's cooldown config if the
+/// invocation counts towards its cooldown.
+#[allow(clippy::needless_lifetimes)] // false positive (clippy issue 7271)
+pub(super) async fn check_access<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+) -> Result<Option<crate::CooldownConfig>, crate::FrameworkError<'a, U, E>> {
+    check_permissions(ctx).await?;
+    check_cooldowns(ctx).await
 }
 
 /// Checks if the invoker is allowed to execute this command at this point in time
 ///
-/// Doesn't actually start the cooldown timer! This should be done by the caller later, after
-/// argument parsing.
+/// Doesn't actually start the cooldown timer! This should be done by the caller later with
+/// [`start_cooldown`], after argument parsing.
 /// (A command that didn't even get past argument parsing shouldn't trigger cooldowns)
+///
+/// Doesn't reserve a [`crate::Command::max_concurrency`] slot either, see
+/// [`acquire_concurrency_permit`].
 #[allow(clippy::needless_lifetimes)] // false positive (clippy issue 7271)
 pub async fn check_permissions_and_cooldown<'a, U, E>(
     ctx: crate::Context<'a, U, E>,
 ) -> Result<(), crate::FrameworkError<'a, U, E>> {
-    for parent_command in ctx.parent_commands() {
-        check_permissions_and_cooldown_single(ctx, parent_command).await?;
+    check_access(ctx).await?;
+    Ok(())
+}
+
+/// Reserves a slot for this invocation if the command has [`crate::Command::max_concurrency`]
+/// set. The caller should hold on to the returned permit until the invocation ends.
+///
+/// If the limit is reached and [`crate::MaxConcurrency::queue`] is set, waits for a free slot, at
+/// most for [`crate::MaxConcurrency::queue_timeout`]. Application commands are deferred before
+/// waiting, so that the interaction doesn't expire in the meantime.
+#[allow(clippy::needless_lifetimes)] // false positive (clippy issue 7271)
+pub async fn acquire_concurrency_permit<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+) -> Result<crate::ConcurrencyPermit, crate::FrameworkError<'a, U, E>> {
+    let command = ctx.command();
+    let max_concurrency = match &command.max_concurrency {
+        Some(x) => x,
+        None => return Ok(crate::ConcurrencyPermit::empty()),
+    };
+    let (tracker, user_id) = (&command.concurrency, ctx.author().id);
+    let (guild_id, channel_id) = (ctx.guild_id(), ctx.channel_id());
+
+    let mut permit = tracker.try_acquire(max_concurrency, user_id, guild_id, channel_id);
+    if permit.is_none() && max_concurrency.queue {
+        if let crate::Context::Application(ctx) = ctx {
+            if let Err(e) = ctx.defer_response(command.ephemeral).await {
+                tracing::warn!("Error when deferring queued invocation: {}", e);
+            }
+        }
+
+        let wait = tracker.acquire(max_concurrency, user_id, guild_id, channel_id);
+        let timeout = max_concurrency
+            .queue_timeout
+            .or(command.timeout)
+            .or(ctx.framework().options().command_timeout);
+        permit = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, wait).await.ok().flatten(),
+            None => wait.await,
+        };
+    }
+    match permit {
+        Some(permit) => Ok(permit),
+        None => Err(crate::FrameworkError::ConcurrencyLimit {
+            max_concurrency,
+            ctx,
+        }),
     }
-    check_permissions_and_cooldown_single(ctx, ctx.command()).await?;
+}
 
-    Ok(())
+/// Runs all checks for an invocation about to be executed: first the permission checks, then
+/// [`acquire_concurrency_permit`], then the cooldowns. Checking the cooldown last makes sure
+/// queued invocations are checked against the cooldowns started while they were waiting.
+///
+/// Returns the concurrency permit, and the command's cooldown config if the invocation counts
+/// towards its cooldown, for [`start_cooldown_with_config`] and [`apply_cooldown_policy`]
+pub(super) async fn prepare_invocation<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+) -> Result<
+    (crate::ConcurrencyPermit, Option<crate::CooldownConfig>),
+    crate::FrameworkError<'a, U, E>,
+> {
+    check_permissions(ctx).await?;
+    let permit = acquire_concurrency_permit(ctx).await?;
+    let cooldown_config = check_cooldowns(ctx).await?;
+    Ok((permit, cooldown_config))
+}
+
+/// Records this invocation in [`crate::FrameworkOptions::cooldown_store`]. For custom dispatch
+/// code which calls [`check_permissions_and_cooldown`] and then runs the command action itself;
+/// the framework's own dispatch does this automatically.
+///
+/// Unlike the framework's dispatch, this doesn't apply [`crate::CooldownConfig::policy`], so
+/// call it only once the invocation should count. Does nothing if it doesn't count anyway, e.g.
+/// because of [`crate::FrameworkOptions::manual_cooldowns`].
+pub async fn start_cooldown<U, E>(ctx: crate::Context<'_, U, E>) {
+    let config = match counted_cooldown_config(ctx, ctx.command()).await {
+        Ok(Some(config)) => config,
+        Ok(None) => return,
+        Err(_) => {
+            tracing::warn!("Error when resolving the cooldown config, not starting the cooldown");
+            return;
+        }
+    };
+    let store = &ctx.framework().options().cooldown_store;
+    let key = &ctx.command().cooldown_key();
+    store
+        .start_cooldown(key, &ctx.cooldown_context(), &config)
+        .await;
+}
+
+/// Records the invocation in [`crate::FrameworkOptions::cooldown_store`] right before the command
+/// action runs, unless the policy is [`crate::CooldownPolicy::OnSuccess`]. `config` is the one
+/// returned by [`prepare_invocation`].
+///
+/// Returns when the invocation was recorded, if it was, for [`apply_cooldown_policy`].
+pub(super) async fn start_cooldown_with_config<U, E>(
+    ctx: crate::Context<'_, U, E>,
+    config: Option<&crate::CooldownConfig>,
+) -> Option<std::time::SystemTime> {
+    let config = match config {
+        Some(config) if config.policy != crate::CooldownPolicy::OnSuccess => config,
+        _ => return None,
+    };
+    let store = &ctx.framework().options().cooldown_store;
+    let key = &ctx.command().cooldown_key();
+    Some(
+        store
+            .start_cooldown(key, &ctx.cooldown_context(), config)
+            .await,
+    )
+}
+
+/// Starts or refunds the cooldown once the command action has finished, as configured by
+/// [`crate::CooldownConfig::policy`]. `result` is the action's result, or Err if it panicked.
+///
+/// The cooldown itself is started by [`start_cooldown_with_config`] before the action runs, unless the policy
+/// is [`crate::CooldownPolicy::OnSuccess`]. `config` is the one returned by
+/// [`prepare_invocation`], `recorded_at` the one returned by [`start_cooldown_with_config`].
+pub(super) async fn apply_cooldown_policy<U, E>(
+    ctx: crate::Context<'_, U, E>,
+    config: Option<&crate::CooldownConfig>,
+    recorded_at: Option<std::time::SystemTime>,
+    result: &Result<Result<(), crate::FrameworkError<'_, U, E>>, Option<String>>,
+) {
+    use crate::CooldownPolicy;
+
+    let config = match config {
+        Some(config) => config,
+        None => return,
+    };
+    let store = &ctx.framework().options().cooldown_store;
+    let key = &ctx.command().cooldown_key();
+    match (config.policy, result, recorded_at) {
+        (CooldownPolicy::OnSuccess, Ok(Ok(())), _) => {
+            store
+                .start_cooldown(key, &ctx.cooldown_context(), config)
+                .await;
+        }
+        // A command that didn't even get past argument parsing shouldn't trigger cooldowns
+        (
+            _,
+            Ok(Err(
+                crate::FrameworkError::ArgumentParse { .. }
+                | crate::FrameworkError::CommandStructureMismatch { .. },
+            )),
+            Some(recorded_at),
+        )
+        | (
+            CooldownPolicy::RefundOnError,
+            Ok(Err(
+                crate::FrameworkError::Command { .. }
+                | crate::FrameworkError::CommandTimeout { .. },
+            ))
+            | Err(_),
+            Some(recorded_at),
+        )
+        | (CooldownPolicy::RefundOnPanic, Err(_), Some(recorded_at)) => {
+            store
+                .refund_cooldown(key, &ctx.cooldown_context(), config, recorded_at)
+                .await;
+        }
+        _ => {}
+    }
+}
+
+/// Runs the command action wrapped in all [`crate::Layer`]s that apply to it, cancelling it if it
+/// exceeds [`crate::Command::timeout`] or [`crate::FrameworkOptions::command_timeout`]
+pub(super) async fn run_action<'a, U, E>(
+    ctx: crate::Context<'a, U, E>,
+    action: crate::BoxFuture<'a, Result<(), crate::FrameworkError<'a, U, E>>>,
+) -> Result<(), crate::FrameworkError<'a, U, E>> {
+    let action = crate::Next::new(ctx, action).run();
+    let timeout = ctx
+        .command()
+        .timeout
+        .or(ctx.framework().options().command_timeout);
+    match timeout {
+        // Dropping the action future on timeout cancels the command at its next await point
+        Some(timeout) => tokio::time::timeout(timeout, action)
+            .await
+            .unwrap_or(Err(crate::FrameworkError::CommandTimeout { timeout, ctx })),
+        None => action.await,
+    }
+}
+
+/// Reports the outcome of an invocation to [`crate::FrameworkOptions::on_invocation_finished`]
+pub(super) async fn finish_invocation<U, E>(
+    ctx: crate::Context<'_, U, E>,
+    result: &Result<(), crate::FrameworkError<'_, U, E>>,
+    duration: std::time::Duration,
+) {
+    let outcome = match result {
+        Ok(()) => crate::InvocationOutcome::Success,
+        Err(error) => error.outcome(),
+    };
+    // The invocation span, see [`invocation_span`]
+    tracing::Span::current().record("outcome", tracing::field::debug(outcome));
+    let options = ctx.framework().options();
+    options
+        .metrics
+        .record_invocation(&ctx.command().qualified_name, outcome, duration);
+    (options.on_invocation_finished)(ctx, outcome, duration).await;
+}
+
+/// Creates the span that an invocation runs in, so that everything the command does, including its
+/// HTTP requests, can be correlated. The `outcome` field is filled in once the invocation finishes
+pub(super) fn invocation_span<U, E>(
+    ctx: crate::Context<'_, U, E>,
+    trigger: impl std::fmt::Debug,
+) -> tracing::Span {
+    // Context::id needs a datetime library
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    let id = Some(ctx.id());
+    #[cfg(not(any(feature = "chrono", feature = "time")))]
+    let id: Option<u64> = None;
+
+    tracing::info_span!(
+        "invocation",
+        command = %ctx.command().qualified_name,
+        id,
+        guild_id = ctx.guild_id().map(|id| id.get()),
+        channel_id = ctx.channel_id().get(),
+        user_id = ctx.author().id.get(),
+        trigger = ?trigger,
+        outcome = tracing::field::Empty,
+    )
 }
@@ -0,0 +1,76 @@
+//! Dispatches message component and modal interactions onto [`crate::ComponentHandler`]s and
+//! [`crate::ModalHandler`]s
+
+use crate::serenity_prelude as serenity;
+
+/// Finds the first [`crate::ComponentHandler`] whose pattern matches the interaction's custom ID
+/// and runs it.
+///
+/// Returns `Ok(false)` if no handler matched, which leaves the interaction to collectors or
+/// [`crate::FrameworkOptions::event_handler`].
+pub async fn dispatch_component<'a, U, E>(
+    framework: crate::FrameworkContext<'a, U, E>,
+    ctx: &'a serenity::Context,
+    interaction: &'a serenity::ComponentInteraction,
+    has_sent_initial_response: &'a std::sync::atomic::AtomicBool,
+) -> Result<bool, crate::FrameworkError<'a, U, E>> {
+    let custom_id = &interaction.data.custom_id;
+    let (handler, args) = match framework
+        .options
+        .component_handlers
+        .iter()
+        .find_map(|handler| Some((handler, handler.custom_id.strip(custom_id)?)))
+    {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+
+    let ctx = crate::ComponentContext {
+        serenity_context: ctx,
+        interaction,
+        args,
+        has_sent_initial_response,
+        framework,
+        data: framework.user_data,
+        __non_exhaustive: (),
+    };
+    (handler.action)(ctx)
+        .await
+        .map_err(|error| crate::FrameworkError::ComponentHandler { error, ctx })?;
+    Ok(true)
+}
+
+/// Finds the first [`crate::ModalHandler`] whose pattern matches the submission's custom ID, parses
+/// the submission and runs the handler.
+///
+/// Returns `Ok(false)` if no handler matched, which leaves the submission to collectors (like in
+/// [`crate::execute_modal`]) or [`crate::FrameworkOptions::event_handler`].
+pub async fn dispatch_modal<'a, U, E>(
+    framework: crate::FrameworkContext<'a, U, E>,
+    ctx: &'a serenity::Context,
+    interaction: &'a serenity::ModalInteraction,
+    has_sent_initial_response: &'a std::sync::atomic::AtomicBool,
+) -> Result<bool, crate::FrameworkError<'a, U, E>> {
+    let custom_id = &interaction.data.custom_id;
+    let (handler, args) = match framework
+        .options
+        .modal_handlers
+        .iter()
+        .find_map(|handler| Some((handler, handler.custom_id.strip(custom_id)?)))
+    {
+        Some(x) => x,
+        None => return Ok(false),
+    };
+
+    let ctx = crate::ModalContext {
+        serenity_context: ctx,
+        interaction,
+        args,
+        has_sent_initial_response,
+        framework,
+        data: framework.user_data,
+        __non_exhaustive: (),
+    };
+    (handler.action)(ctx).await?;
+    Ok(true)
+}
@@ -1,10 +1,12 @@
 //! Contains all code to dispatch incoming events onto framework commands
 
 mod common;
+mod component;
 mod prefix;
 mod slash;
 
 pub use common::*;
+pub use component::*;
 pub use prefix::*;
 pub use slash::*;
 
@@ -40,6 +42,13 @@ impl<'a, U, E> FrameworkContext<'a, U, E> {
         self.options
     }
 
+    /// Returns the registry to enable, disable, add or remove commands at runtime
+    ///
+    /// Shorthand for `&self.options.registry`
+    pub fn registry(&self) -> &'a crate::CommandRegistry<U, E> {
+        &self.options.registry
+    }
+
     /// Returns the serenity's client shard manager.
     ///
     /// This function exists for API compatiblity with [`crate::Framework`]. On this type, you can
@@ -67,6 +76,7 @@ pub async fn dispatch_event<U: Send + Sync, E>(
     match &event {
         serenity::FullEvent::Message { new_message } => {
             let invocation_data = tokio::sync::Mutex::new(Box::new(()) as _);
+            let commands = framework.options.active_commands();
             let mut parent_commands = Vec::new();
             let trigger = crate::MessageDispatchTrigger::MessageCreate;
             if let Err(error) = prefix::dispatch_message(
@@ -75,6 +85,7 @@ pub async fn dispatch_event<U: Send + Sync, E>(
                 new_message,
                 trigger,
                 &invocation_data,
+                &commands,
                 &mut parent_commands,
             )
             .await
@@ -94,6 +105,7 @@ pub async fn dispatch_event<U: Send + Sync, E>(
 
                 if let Some((msg, previously_tracked)) = msg {
                     let invocation_data = tokio::sync::Mutex::new(Box::new(()) as _);
+                    let commands = framework.options.active_commands();
                     let mut parent_commands = Vec::new();
                     let trigger = match previously_tracked {
                         true => crate::MessageDispatchTrigger::MessageEdit,
@@ -105,6 +117,7 @@ pub async fn dispatch_event<U: Send + Sync, E>(
                         &msg,
                         trigger,
                         &invocation_data,
+                        &commands,
                         &mut parent_commands,
                     )
                     .await
@@ -133,6 +146,7 @@ pub async fn dispatch_event<U: Send + Sync, E>(
             interaction: serenity::Interaction::Command(interaction),
         } => {
             let invocation_data = tokio::sync::Mutex::new(Box::new(()) as _);
+            let commands = framework.options.active_commands();
             let mut parent_commands = Vec::new();
             if let Err(error) = slash::dispatch_interaction(
                 framework,
@@ -141,6 +155,7 @@ pub async fn dispatch_event<U: Send + Sync, E>(
                 &std::sync::atomic::AtomicBool::new(false),
                 &invocation_data,
                 &interaction.data.options(),
+                &commands,
                 &mut parent_commands,
             )
             .await
@@ -152,6 +167,7 @@ pub async fn dispatch_event<U: Send + Sync, E>(
             interaction: serenity::Interaction::Autocomplete(interaction),
         } => {
             let invocation_data = tokio::sync::Mutex::new(Box::new(()) as _);
+            let commands = framework.options.active_commands();
             let mut parent_commands = Vec::new();
             if let Err(error) = slash::dispatch_autocomplete(
                 framework,
@@ -160,6 +176,7 @@ pub async fn dispatch_event<U: Send + Sync, E>(
                 &std::sync::atomic::AtomicBool::new(false),
                 &invocation_data,
                 &interaction.data.options(),
+                &commands,
                 &mut parent_commands,
             )
             .await
@@ -167,6 +184,34 @@ pub async fn dispatch_event<U: Send + Sync, E>(
                 error.handle(framework.options).await;
             }
         }
+        serenity::FullEvent::InteractionCreate {
+            interaction: serenity::Interaction::Component(interaction),
+        } => {
+            if let Err(error) = component::dispatch_component(
+                framework,
+                ctx,
+                interaction,
+                &std::sync::atomic::AtomicBool::new(false),
+            )
+            .await
+            {
+                error.handle(framework.options).await;
+            }
+        }
+        serenity::FullEvent::InteractionCreate {
+            interaction: serenity::Interaction::Modal(interaction),
+        } => {
+            if let Err(error) = component::dispatch_modal(
+                framework,
+                ctx,
+                interaction,
+                &std::sync::atomic::AtomicBool::new(false),
+            )
+            .await
+            {
+                error.handle(framework.options).await;
+            }
+        }
         _ => {}
     }
 
@@ -1,6 +1,7 @@
 //! Dispatches incoming messages and message edits onto framework commands
 
 use crate::serenity_prelude as serenity;
+use tracing::Instrument as _;
 
 /// Checks if this message is a bot invocation by attempting to strip the prefix
 ///
@@ -139,7 +140,7 @@ async fn strip_prefix<'a, U, E>(
 /// );
 /// assert!(parent_commands.is_empty());
 pub fn find_command<'a, U, E>(
-    commands: &'a [crate::Command<U, E>],
+    commands: impl IntoIterator<Item = &'a crate::Command<U, E>>,
     remaining_message: &'a str,
     case_insensitive: bool,
     parent_commands: &mut Vec<&'a crate::Command<U, E>>,
@@ -190,6 +191,7 @@ pub async fn dispatch_message<'a, U: Send + Sync, E>(
     msg: &'a serenity::Message,
     trigger: crate::MessageDispatchTrigger,
     invocation_data: &'a tokio::sync::Mutex<Box<dyn std::any::Any + Send + Sync>>,
+    commands: &'a crate::ActiveCommands<'a, U, E>,
     parent_commands: &'a mut Vec<&'a crate::Command<U, E>>,
 ) -> Result<(), crate::FrameworkError<'a, U, E>> {
     if let Some(ctx) = parse_invocation(
@@ -198,16 +200,13 @@ pub async fn dispatch_message<'a, U: Send + Sync, E>(
         msg,
         trigger,
         invocation_data,
+        commands,
         parent_commands,
     )
     .await?
     {
-        crate::catch_unwind_maybe(run_invocation(ctx))
-            .await
-            .map_err(|payload| crate::FrameworkError::CommandPanic {
-                payload,
-                ctx: ctx.into(),
-            })??;
+        let span = super::common::invocation_span(ctx.into(), ctx.trigger);
+        run_invocation(ctx).instrument(span).await?;
     } else if let Some(non_command_message) = framework.options.prefix_options.non_command_message {
         non_command_message(&framework, ctx, msg)
             .await
@@ -233,6 +232,7 @@ pub async fn parse_invocation<'a, U: Send + Sync, E>(
     msg: &'a serenity::Message,
     trigger: crate::MessageDispatchTrigger,
     invocation_data: &'a tokio::sync::Mutex<Box<dyn std::any::Any + Send + Sync>>,
+    commands: &'a crate::ActiveCommands<'a, U, E>,
     parent_commands: &'a mut Vec<&'a crate::Command<U, E>>,
 ) -> Result<Option<crate::PrefixContext<'a, U, E>>, crate::FrameworkError<'a, U, E>> {
     // Check if we're allowed to invoke from bot messages
@@ -261,7 +261,7 @@ pub async fn parse_invocation<'a, U: Send + Sync, E>(
     let msg_content = msg_content.trim_start();
 
     let (command, invoked_command_name, args) = find_command(
-        &framework.options.commands,
+        commands,
         msg_content,
         framework.options.prefix_options.case_insensitive_commands,
         parent_commands,
@@ -314,6 +314,28 @@ pub async fn run_invocation<U, E>(
         return Ok(());
     }
 
+    if super::common::check_blocklist(ctx.into(), true).await {
+        return Ok(());
+    }
+
+    let start = std::time::Instant::now();
+    // Catches panics outside the command action too, e.g. in checks and hooks, so that they're
+    // reported as the invocation's outcome
+    let result = match crate::catch_unwind_maybe(execute_invocation(ctx)).await {
+        Ok(result) => result,
+        Err(payload) => Err(crate::FrameworkError::CommandPanic {
+            payload,
+            ctx: ctx.into(),
+        }),
+    };
+    super::common::finish_invocation(ctx.into(), &result, start.elapsed()).await;
+    result
+}
+
+/// See [`run_invocation`]. Runs the checks, the command and its hooks
+async fn execute_invocation<U, E>(
+    ctx: crate::PrefixContext<'_, U, E>,
+) -> Result<(), crate::FrameworkError<'_, U, E>> {
     if ctx.command.subcommand_required {
         // None of this command's subcommands were invoked, or else we'd have the subcommand in
         // ctx.command and not the parent command
@@ -322,7 +344,9 @@ pub async fn run_invocation<U, E>(
         });
     }
 
-    super::common::check_permissions_and_cooldown(ctx.into()).await?;
+    // Holds this invocation's concurrency slot until the end of the function
+    let (_concurrency_permit, cooldown_config) =
+        super::common::prepare_invocation(ctx.into()).await?;
 
     // Typing is broadcasted as long as this object is alive
     let _typing_broadcaster = if ctx.command.broadcast_typing {
@@ -338,14 +362,26 @@ pub async fn run_invocation<U, E>(
     // execute_untracked_edits situation and start an infinite loop
     // Reported by vicky5124 https://discord.com/channels/381880193251409931/381912587505500160/897981367604903966
     if let Some(edit_tracker) = &ctx.framework.options.prefix_options.edit_tracker {
-        edit_tracker
-            .write()
-            .unwrap()
-            .track_command(ctx.msg, ctx.command.track_deletion);
+        let mut edit_tracker = edit_tracker.write().unwrap();
+        edit_tracker.track_command(ctx.msg, ctx.command.track_deletion);
+        let size = edit_tracker.len();
+        drop(edit_tracker);
+        ctx.framework.options.metrics.record_edit_tracker_size(size);
     }
 
-    // Execute command
-    (ctx.action)(ctx).await?;
+    // Execute command. Panics are caught here so that the cooldown can be refunded and the panic
+    // is reported as the invocation's outcome
+    let recorded_at =
+        super::common::start_cooldown_with_config(ctx.into(), cooldown_config.as_ref()).await;
+    let action = super::common::run_action(ctx.into(), (ctx.action)(ctx));
+    let action_result = crate::catch_unwind_maybe(action).await;
+    let cooldown_config = cooldown_config.as_ref();
+    super::common::apply_cooldown_policy(ctx.into(), cooldown_config, recorded_at, &action_result)
+        .await;
+    action_result.map_err(|payload| crate::FrameworkError::CommandPanic {
+        payload,
+        ctx: ctx.into(),
+    })??;
 
     (ctx.framework.options.post_command)(crate::Context::Prefix(ctx)).await;
 
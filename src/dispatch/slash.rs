@@ -1,15 +1,16 @@
 //! Dispatches interactions onto framework commands
 
 use crate::serenity_prelude as serenity;
+use tracing::Instrument as _;
 
 /// Check if the interaction with the given name and arguments matches any framework command
 fn find_matching_command<'a, 'b, U, E>(
     interaction_name: &str,
     interaction_options: &'b [serenity::ResolvedOption<'b>],
-    commands: &'a [crate::Command<U, E>],
+    commands: impl IntoIterator<Item = &'a crate::Command<U, E>>,
     parent_commands: &mut Vec<&'a crate::Command<U, E>>,
 ) -> Option<(&'a crate::Command<U, E>, &'b [serenity::ResolvedOption<'b>])> {
-    commands.iter().find_map(|cmd| {
+    commands.into_iter().find_map(|cmd| {
         if interaction_name != cmd.name
             && Some(interaction_name) != cmd.context_menu_name.as_deref()
         {
@@ -46,14 +47,11 @@ fn extract_command<'a, U, E>(
     has_sent_initial_response: &'a std::sync::atomic::AtomicBool,
     invocation_data: &'a tokio::sync::Mutex<Box<dyn std::any::Any + Send + Sync>>,
     options: &'a [serenity::ResolvedOption<'a>],
+    commands: &'a crate::ActiveCommands<'a, U, E>,
     parent_commands: &'a mut Vec<&'a crate::Command<U, E>>,
 ) -> Result<crate::ApplicationContext<'a, U, E>, crate::FrameworkError<'a, U, E>> {
-    let search_result = find_matching_command(
-        &interaction.data.name,
-        options,
-        &framework.options.commands,
-        parent_commands,
-    );
+    let search_result =
+        find_matching_command(&interaction.data.name, options, commands, parent_commands);
     let (command, leaf_interaction_options) =
         search_result.ok_or(crate::FrameworkError::UnknownInteraction {
             ctx,
@@ -77,6 +75,9 @@ fn extract_command<'a, U, E>(
 }
 
 /// Given an interaction, finds the matching framework command and checks if the user is allowed access
+///
+/// Invokers on [`crate::FrameworkOptions::blocklist`] are rejected with
+/// [`crate::FrameworkError::CommandCheckFailed`] without an error or denial.
 #[allow(clippy::too_many_arguments)] // We need to pass them all in to create Context.
 pub async fn extract_command_and_run_checks<'a, U, E>(
     framework: crate::FrameworkContext<'a, U, E>,
@@ -86,6 +87,7 @@ pub async fn extract_command_and_run_checks<'a, U, E>(
     has_sent_initial_response: &'a std::sync::atomic::AtomicBool,
     invocation_data: &'a tokio::sync::Mutex<Box<dyn std::any::Any + Send + Sync>>,
     options: &'a [serenity::ResolvedOption<'a>],
+    commands: &'a crate::ActiveCommands<'a, U, E>,
     parent_commands: &'a mut Vec<&'a crate::Command<U, E>>,
 ) -> Result<crate::ApplicationContext<'a, U, E>, crate::FrameworkError<'a, U, E>> {
     let ctx = extract_command(
@@ -96,9 +98,18 @@ pub async fn extract_command_and_run_checks<'a, U, E>(
         has_sent_initial_response,
         invocation_data,
         options,
+        commands,
         parent_commands,
     )?;
-    super::common::check_permissions_and_cooldown(ctx.into()).await?;
+    // Doesn't respond, like autocomplete. The caller decides how to handle the rejection
+    if super::common::check_blocklist(ctx.into(), false).await {
+        return Err(crate::FrameworkError::CommandCheckFailed {
+            error: None,
+            denial: None,
+            ctx: ctx.into(),
+        });
+    }
+    super::common::check_access(ctx.into()).await?;
     Ok(ctx)
 }
 
@@ -107,7 +118,31 @@ pub async fn extract_command_and_run_checks<'a, U, E>(
 async fn run_command<U, E>(
     ctx: crate::ApplicationContext<'_, U, E>,
 ) -> Result<(), crate::FrameworkError<'_, U, E>> {
-    super::common::check_permissions_and_cooldown(ctx.into()).await?;
+    if super::common::check_blocklist(ctx.into(), true).await {
+        return Ok(());
+    }
+
+    let start = std::time::Instant::now();
+    // Catches panics outside the command action too, e.g. in checks and hooks, so that they're
+    // reported as the invocation's outcome
+    let result = match crate::catch_unwind_maybe(execute_command(ctx)).await {
+        Ok(result) => result,
+        Err(payload) => Err(crate::FrameworkError::CommandPanic {
+            payload,
+            ctx: ctx.into(),
+        }),
+    };
+    super::common::finish_invocation(ctx.into(), &result, start.elapsed()).await;
+    result
+}
+
+/// See [`run_command`]. Runs the checks, the command and its hooks
+async fn execute_command<U, E>(
+    ctx: crate::ApplicationContext<'_, U, E>,
+) -> Result<(), crate::FrameworkError<'_, U, E>> {
+    // Holds this invocation's concurrency slot until the end of the function
+    let (_concurrency_permit, cooldown_config) =
+        super::common::prepare_invocation(ctx.into()).await?;
 
     (ctx.framework.options.pre_command)(crate::Context::Application(ctx)).await;
 
@@ -118,13 +153,24 @@ async fn run_command<U, E>(
         description: "received interaction type but command contained no \
                 matching action or interaction contained no matching context menu object",
     };
-    let action_result = match ctx.interaction.data.kind {
+    let kind = ctx.interaction.data.kind;
+    if !matches!(
+        kind,
+        serenity::CommandType::ChatInput
+            | serenity::CommandType::User
+            | serenity::CommandType::Message
+    ) {
+        tracing::warn!("unknown interaction command type: {:?}", kind);
+        return Ok(());
+    }
+
+    let action = match kind {
         serenity::CommandType::ChatInput => {
             let action = ctx
                 .command
                 .slash_action
                 .ok_or(command_structure_mismatch_error)?;
-            action(ctx).await
+            action(ctx)
         }
         serenity::CommandType::User => {
             match (
@@ -134,11 +180,12 @@ async fn run_command<U, E>(
                 (
                     Some(crate::ContextMenuCommandAction::User(action)),
                     Some(serenity::ResolvedTarget::User(user, _)),
-                ) => action(ctx, (*user).clone()).await,
+                ) => action(ctx, (*user).clone()),
                 _ => return Err(command_structure_mismatch_error),
             }
         }
-        serenity::CommandType::Message => {
+        // serenity::CommandType::Message, the only remaining one as per the check above
+        _ => {
             match (
                 ctx.command.context_menu_action,
                 &ctx.interaction.data.target(),
@@ -146,16 +193,25 @@ async fn run_command<U, E>(
                 (
                     Some(crate::ContextMenuCommandAction::Message(action)),
                     Some(serenity::ResolvedTarget::Message(message)),
-                ) => action(ctx, (*message).clone()).await,
+                ) => action(ctx, (*message).clone()),
                 _ => return Err(command_structure_mismatch_error),
             }
         }
-        other => {
-            tracing::warn!("unknown interaction command type: {:?}", other);
-            return Ok(());
-        }
     };
-    action_result?;
+
+    // Panics are caught here so that the cooldown can be refunded and the panic is reported as
+    // the invocation's outcome
+    let recorded_at =
+        super::common::start_cooldown_with_config(ctx.into(), cooldown_config.as_ref()).await;
+    let action_result =
+        crate::catch_unwind_maybe(super::common::run_action(ctx.into(), action)).await;
+    let cooldown_config = cooldown_config.as_ref();
+    super::common::apply_cooldown_policy(ctx.into(), cooldown_config, recorded_at, &action_result)
+        .await;
+    action_result.map_err(|payload| crate::FrameworkError::CommandPanic {
+        payload,
+        ctx: ctx.into(),
+    })??;
 
     (ctx.framework.options.post_command)(crate::Context::Application(ctx)).await;
 
@@ -163,6 +219,7 @@ async fn run_command<U, E>(
 }
 
 /// Dispatches this interaction onto framework commands, i.e. runs the associated command
+#[allow(clippy::too_many_arguments)] // We need to pass them all in to create Context.
 pub async fn dispatch_interaction<'a, U, E>(
     framework: crate::FrameworkContext<'a, U, E>,
     ctx: &'a serenity::Context,
@@ -173,6 +230,8 @@ pub async fn dispatch_interaction<'a, U, E>(
     invocation_data: &'a tokio::sync::Mutex<Box<dyn std::any::Any + Send + Sync>>,
     // Need to pass this in from outside because of lifetime issues
     options: &'a [serenity::ResolvedOption<'a>],
+    // Need to pass this in from outside because of lifetime issues
+    commands: &'a crate::ActiveCommands<'a, U, E>,
     parent_commands: &'a mut Vec<&'a crate::Command<U, E>>,
 ) -> Result<(), crate::FrameworkError<'a, U, E>> {
     let ctx = extract_command(
@@ -183,15 +242,12 @@ pub async fn dispatch_interaction<'a, U, E>(
         has_sent_initial_response,
         invocation_data,
         options,
+        commands,
         parent_commands,
     )?;
 
-    crate::catch_unwind_maybe(run_command(ctx))
-        .await
-        .map_err(|payload| crate::FrameworkError::CommandPanic {
-            payload,
-            ctx: ctx.into(),
-        })??;
+    let span = super::common::invocation_span(ctx.into(), ctx.interaction_type);
+    run_command(ctx).instrument(span).await?;
 
     Ok(())
 }
@@ -201,7 +257,13 @@ pub async fn dispatch_interaction<'a, U, E>(
 async fn run_autocomplete<U, E>(
     ctx: crate::ApplicationContext<'_, U, E>,
 ) -> Result<(), crate::FrameworkError<'_, U, E>> {
-    super::common::check_permissions_and_cooldown(ctx.into()).await?;
+    // Autocomplete interactions can't be responded to with a message
+    if super::common::check_blocklist(ctx.into(), false).await {
+        return Ok(());
+    }
+
+    // Autocomplete doesn't count towards the command's concurrency limit
+    super::common::check_access(ctx.into()).await?;
 
     // Find which parameter is focused by the user
     let (focused_option_name, partial_input) = match ctx.args.iter().find_map(|o| match &o.value {
@@ -235,7 +297,13 @@ async fn run_autocomplete<U, E>(
     use ::serenity::json::*; // as_str() access via trait for simd-json
 
     // Generate an autocomplete response
-    let autocomplete_response = match autocomplete_callback(ctx, partial_input).await {
+    let start = std::time::Instant::now();
+    let autocomplete_response = autocomplete_callback(ctx, partial_input).await;
+    ctx.framework
+        .options
+        .metrics
+        .record_autocomplete(&ctx.command.qualified_name, start.elapsed());
+    let autocomplete_response = match autocomplete_response {
         Ok(x) => x,
         Err(e) => {
             tracing::warn!("couldn't generate autocomplete response: {e}");
@@ -260,6 +328,7 @@ async fn run_autocomplete<U, E>(
 
 /// Dispatches this interaction onto framework commands, i.e. runs the associated autocomplete
 /// callback
+#[allow(clippy::too_many_arguments)] // We need to pass them all in to create Context.
 pub async fn dispatch_autocomplete<'a, U, E>(
     framework: crate::FrameworkContext<'a, U, E>,
     ctx: &'a serenity::Context,
@@ -268,6 +337,7 @@ pub async fn dispatch_autocomplete<'a, U, E>(
     has_sent_initial_response: &'a std::sync::atomic::AtomicBool,
     invocation_data: &'a tokio::sync::Mutex<Box<dyn std::any::Any + Send + Sync>>,
     options: &'a [serenity::ResolvedOption<'a>],
+    commands: &'a crate::ActiveCommands<'a, U, E>,
     parent_commands: &'a mut Vec<&'a crate::Command<U, E>>,
 ) -> Result<(), crate::FrameworkError<'a, U, E>> {
     let ctx = extract_command(
@@ -278,15 +348,25 @@ pub async fn dispatch_autocomplete<'a, U, E>(
         has_sent_initial_response,
         invocation_data,
         options,
+        commands,
         parent_commands,
     )?;
 
-    crate::catch_unwind_maybe(run_autocomplete(ctx))
+    let span = super::common::invocation_span(ctx.into(), ctx.interaction_type);
+    let result = match crate::catch_unwind_maybe(run_autocomplete(ctx))
+        .instrument(span.clone())
         .await
-        .map_err(|payload| crate::FrameworkError::CommandPanic {
+    {
+        Ok(result) => result,
+        Err(payload) => Err(crate::FrameworkError::CommandPanic {
             payload,
             ctx: ctx.into(),
-        })??;
-
-    Ok(())
+        }),
+    };
+    let outcome = match &result {
+        Ok(()) => crate::InvocationOutcome::Success,
+        Err(error) => error.outcome(),
+    };
+    span.record("outcome", tracing::field::debug(outcome));
+    result
 }
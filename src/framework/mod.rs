@@ -3,17 +3,22 @@
 use std::sync::Arc;
 
 pub use builder::*;
+pub use validate::*;
 
 use crate::{serenity_prelude as serenity, BoxFuture};
 
 mod builder;
+mod validate;
 
 /// The main framework struct which stores all data and handles message and interaction dispatch.
 ///
 /// Technically, this is just an optional abstraction over [`crate::dispatch_event`] with some
 /// additional conveniences built-in:
 /// - fills in correct values for [`crate::Command::qualified_name`]: [`set_qualified_names`]
+/// - warns about commands that Discord would reject on registration: [`validate_commands`]
 /// - spawns a background task to periodically clear edit tracker cache
+/// - spawns a background task to periodically purge expired cooldowns from
+///   [`crate::FrameworkOptions::cooldown_store`]
 /// - sets up user data on the first Ready event
 /// - keeps track of shard manager and bot ID automatically
 ///
@@ -45,6 +50,8 @@ pub struct Framework<U, E> {
 
     /// Handle to the background task in order to `abort()` it on `Drop`
     edit_tracker_purge_task: Option<tokio::task::JoinHandle<()>>,
+    /// Handle to the cooldown purge task in order to `abort()` it on `Drop`
+    cooldown_purge_task: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl<U, E> Framework<U, E> {
@@ -87,6 +94,7 @@ impl<U, E> Framework<U, E> {
             bot_id: std::sync::OnceLock::new(),
             setup: std::sync::Mutex::new(Some(Box::new(setup))),
             edit_tracker_purge_task: None,
+            cooldown_purge_task: None,
             shard_manager: None,
             options,
         }
@@ -122,6 +130,9 @@ impl<U, E> Drop for Framework<U, E> {
         if let Some(task) = &mut self.edit_tracker_purge_task {
             task.abort()
         }
+        if let Some(task) = &mut self.cooldown_purge_task {
+            task.abort()
+        }
     }
 }
 
@@ -129,6 +140,9 @@ impl<U, E> Drop for Framework<U, E> {
 impl<U: Send + Sync, E: Send + Sync> serenity::Framework for Framework<U, E> {
     async fn init(&mut self, client: &serenity::Client) {
         set_qualified_names(&mut self.options.commands);
+        for error in validate_commands(&self.options.commands) {
+            tracing::warn!("Invalid command: {error}");
+        }
 
         message_content_intent_sanity_check(
             &self.options.prefix_options,
@@ -144,9 +158,14 @@ impl<U: Send + Sync, E: Send + Sync> serenity::Framework for Framework<U, E> {
         }
 
         if let Some(edit_tracker) = &self.options.prefix_options.edit_tracker {
-            self.edit_tracker_purge_task =
-                Some(spawn_edit_tracker_purge_task(edit_tracker.clone()));
+            self.edit_tracker_purge_task = Some(spawn_edit_tracker_purge_task(
+                edit_tracker.clone(),
+                self.options.metrics.clone(),
+            ));
         }
+        self.cooldown_purge_task = Some(spawn_cooldown_purge_task(
+            self.options.cooldown_store.clone(),
+        ));
     }
 
     async fn dispatch(&self, ctx: serenity::Context, event: serenity::FullEvent) {
@@ -265,13 +284,32 @@ pub async fn insert_owners_from_http(
 /// 'static
 fn spawn_edit_tracker_purge_task(
     edit_tracker: Arc<std::sync::RwLock<crate::EditTracker>>,
+    metrics: Arc<dyn crate::MetricsSink>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         loop {
-            edit_tracker.write().unwrap().purge();
+            let size = {
+                let mut edit_tracker = edit_tracker.write().unwrap();
+                edit_tracker.purge();
+                edit_tracker.len()
+            };
+            metrics.record_edit_tracker_size(size);
 
             // not sure if the purging interval should be configurable
             tokio::time::sleep(std::time::Duration::from_secs(60)).await;
         }
     })
 }
+
+/// Spawns a background task which periodically removes expired cooldowns from the store
+fn spawn_cooldown_purge_task(
+    cooldown_store: Arc<dyn crate::CooldownStore>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            cooldown_store.purge_expired().await;
+
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        }
+    })
+}
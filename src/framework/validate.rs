@@ -0,0 +1,495 @@
+//! Checks the command tree against Discord's application command constraints
+
+use crate::serenity_prelude as serenity;
+
+/// Maximum number of options (parameters or subcommands) per command or subcommand group
+const MAX_OPTIONS: usize = 25;
+/// Maximum number of choices per choice parameter
+const MAX_CHOICES: usize = 25;
+/// Maximum length of command, parameter and context menu names
+const MAX_NAME_LENGTH: usize = 32;
+/// Maximum length of command and parameter descriptions, and of choice names
+const MAX_DESCRIPTION_LENGTH: usize = 100;
+
+/// Which rule a command violates. Contained in [`CommandValidationError`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandValidationErrorKind {
+    /// A name (or its localization for `locale`) is empty, longer than 32 characters or contains
+    /// characters other than letters, digits, `-` and `_`
+    InvalidName {
+        /// The offending name
+        name: String,
+        /// The locale of the offending localization, if it's not the default name
+        locale: Option<String>,
+    },
+    /// A slash command or parameter name (or its localization for `locale`) contains uppercase
+    /// characters
+    UppercaseName {
+        /// The offending name
+        name: String,
+        /// The locale of the offending localization, if it's not the default name
+        locale: Option<String>,
+    },
+    /// A description (or its localization for `locale`) is empty or longer than 100 characters
+    InvalidDescriptionLength {
+        /// Length of the description in characters
+        length: usize,
+        /// The locale of the offending localization, if it's not the default description
+        locale: Option<String>,
+    },
+    /// A command has more than 25 parameters or subcommands
+    TooManyOptions {
+        /// Number of parameters or subcommands
+        count: usize,
+    },
+    /// A choice parameter has more than 25 choices
+    TooManyChoices {
+        /// Number of choices
+        count: usize,
+    },
+    /// A choice label is empty or longer than 100 characters
+    InvalidChoiceName {
+        /// The offending choice label
+        name: String,
+    },
+    /// Subcommands are nested deeper than command → subcommand group → subcommand
+    NestingTooDeep,
+    /// Two siblings (commands, subcommands or parameters) share the same name
+    DuplicateName {
+        /// The duplicated name
+        name: String,
+    },
+    /// A required parameter comes after an optional one
+    RequiredAfterOptional,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// A single diagnostic emitted by [`validate_commands`], pointing at the exact command and
+/// parameter which violates Discord's constraints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandValidationError {
+    /// Full name of the offending command including parent command names, e.g. `config set`
+    pub qualified_name: String,
+    /// Name of the offending parameter, if the diagnostic concerns a parameter
+    pub parameter: Option<String>,
+    /// What's wrong
+    pub kind: CommandValidationErrorKind,
+    // #[non_exhaustive] forbids struct update syntax for ?? reason
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl std::fmt::Display for CommandValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "command `{}`", self.qualified_name)?;
+        if let Some(parameter) = &self.parameter {
+            write!(f, ", parameter `{}`", parameter)?;
+        }
+        f.write_str(": ")?;
+
+        let locale_suffix = |locale: &Option<String>| match locale {
+            Some(locale) => format!(" (locale {})", locale),
+            None => String::new(),
+        };
+        match &self.kind {
+            CommandValidationErrorKind::InvalidName { name, locale } => write!(
+                f,
+                "name `{}`{} must be 1-32 letters, digits, `-` or `_`",
+                name,
+                locale_suffix(locale)
+            ),
+            CommandValidationErrorKind::UppercaseName { name, locale } => write!(
+                f,
+                "name `{}`{} must be lowercase",
+                name,
+                locale_suffix(locale)
+            ),
+            CommandValidationErrorKind::InvalidDescriptionLength { length, locale } => write!(
+                f,
+                "description{} is {} characters long, must be 1-100",
+                locale_suffix(locale),
+                length
+            ),
+            CommandValidationErrorKind::TooManyOptions { count } => write!(
+                f,
+                "has {} parameters or subcommands, at most 25 are allowed",
+                count
+            ),
+            CommandValidationErrorKind::TooManyChoices { count } => {
+                write!(f, "has {} choices, at most 25 are allowed", count)
+            }
+            CommandValidationErrorKind::InvalidChoiceName { name } => {
+                write!(f, "choice `{}` must be 1-100 characters long", name)
+            }
+            CommandValidationErrorKind::NestingTooDeep => f.write_str(
+                "subcommands may only be nested as command, subcommand group, subcommand",
+            ),
+            CommandValidationErrorKind::DuplicateName { name } => {
+                write!(f, "name `{}` is used more than once", name)
+            }
+            CommandValidationErrorKind::RequiredAfterOptional => {
+                f.write_str("required parameter comes after an optional parameter")
+            }
+            CommandValidationErrorKind::__NonExhaustive => unreachable!(),
+        }
+    }
+}
+
+impl std::error::Error for CommandValidationError {}
+
+/// Walks the given commands and checks every application command, subcommand, parameter and
+/// choice against Discord's documented constraints: name format and length, description length,
+/// option and choice counts, subcommand nesting depth, unique names among siblings and required
+/// parameters preceding optional ones.
+///
+/// Prefix-only commands are skipped. Returns an empty list if everything checks out. This is run
+/// by [`crate::Framework`] on startup, which logs each diagnostic as a warning; call it yourself
+/// (e.g. in a unit test) to fail hard instead.
+///
+/// ```rust
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # type Context<'a> = poise::Context<'a, (), Error>;
+/// /// Pings
+/// #[poise::command(slash_command, rename = "Ping")]
+/// async fn ping(ctx: Context<'_>) -> Result<(), Error> { Ok(()) }
+///
+/// let errors = poise::validate_commands(&[ping()]);
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].to_string(), "command `Ping`: name `Ping` must be lowercase");
+/// ```
+pub fn validate_commands<U, E>(commands: &[crate::Command<U, E>]) -> Vec<CommandValidationError> {
+    let mut errors = Vec::new();
+
+    let slash_commands = commands.iter().filter(|c| c.slash_action.is_some());
+    check_unique_names(
+        &mut errors,
+        "",
+        false,
+        slash_commands.clone().map(|c| c.name.as_str()),
+    );
+    for command in slash_commands {
+        validate_slash_command(&mut errors, command, &command.name, 0);
+    }
+
+    for kind in [serenity::CommandType::User, serenity::CommandType::Message] {
+        let context_menu_commands = commands.iter().filter(|c| {
+            matches!(
+                (c.context_menu_action, kind),
+                (
+                    Some(crate::ContextMenuCommandAction::User(_)),
+                    serenity::CommandType::User
+                ) | (
+                    Some(crate::ContextMenuCommandAction::Message(_)),
+                    serenity::CommandType::Message
+                )
+            )
+        });
+        let names = context_menu_commands
+            .clone()
+            .map(|c| c.context_menu_name.as_deref().unwrap_or(&c.name));
+        check_unique_names(&mut errors, "", false, names);
+        for command in context_menu_commands {
+            let name = command
+                .context_menu_name
+                .as_deref()
+                .unwrap_or(&command.name);
+            let length = name.chars().count();
+            if length == 0 || length > MAX_NAME_LENGTH {
+                errors.push(CommandValidationError {
+                    qualified_name: command.name.clone(),
+                    parameter: None,
+                    kind: CommandValidationErrorKind::InvalidName {
+                        name: name.to_owned(),
+                        locale: None,
+                    },
+                    __non_exhaustive: (),
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+/// Checks a slash command or subcommand at the given nesting depth (0 for top-level commands) and
+/// recurses into its subcommands
+fn validate_slash_command<U, E>(
+    errors: &mut Vec<CommandValidationError>,
+    command: &crate::Command<U, E>,
+    qualified_name: &str,
+    depth: usize,
+) {
+    let error = |kind| CommandValidationError {
+        qualified_name: qualified_name.to_owned(),
+        parameter: None,
+        kind,
+        __non_exhaustive: (),
+    };
+
+    check_name(errors, qualified_name, None, &command.name, None);
+    for (locale, name) in &command.name_localizations {
+        check_name(errors, qualified_name, None, name, Some(locale));
+    }
+    if let Some(description) = &command.description {
+        check_description(errors, qualified_name, None, description, None);
+    }
+    for (locale, description) in &command.description_localizations {
+        check_description(errors, qualified_name, None, description, Some(locale));
+    }
+
+    let subcommands = command
+        .subcommands
+        .iter()
+        .filter(|c| c.slash_action.is_some());
+    if command.subcommands.is_empty() {
+        if command.parameters.len() > MAX_OPTIONS {
+            errors.push(error(CommandValidationErrorKind::TooManyOptions {
+                count: command.parameters.len(),
+            }));
+        }
+        check_unique_names(
+            errors,
+            qualified_name,
+            true,
+            command.parameters.iter().map(|p| p.name.as_str()),
+        );
+
+        let mut seen_optional = false;
+        for parameter in &command.parameters {
+            validate_parameter(errors, qualified_name, parameter);
+            if !parameter.required {
+                seen_optional = true;
+            } else if seen_optional {
+                errors.push(CommandValidationError {
+                    parameter: Some(parameter.name.clone()),
+                    ..error(CommandValidationErrorKind::RequiredAfterOptional)
+                });
+            }
+        }
+    } else {
+        // Top-level command → subcommand group → subcommand; anything deeper is rejected
+        if depth >= 2 {
+            errors.push(error(CommandValidationErrorKind::NestingTooDeep));
+            return;
+        }
+
+        let count = subcommands.clone().count();
+        if count > MAX_OPTIONS {
+            errors.push(error(CommandValidationErrorKind::TooManyOptions { count }));
+        }
+        check_unique_names(
+            errors,
+            qualified_name,
+            false,
+            subcommands.clone().map(|c| c.name.as_str()),
+        );
+        for subcommand in subcommands {
+            let qualified_name = format!("{} {}", qualified_name, subcommand.name);
+            validate_slash_command(errors, subcommand, &qualified_name, depth + 1);
+        }
+    }
+}
+
+/// Checks a single slash command parameter and its choices
+fn validate_parameter<U, E>(
+    errors: &mut Vec<CommandValidationError>,
+    qualified_name: &str,
+    parameter: &crate::CommandParameter<U, E>,
+) {
+    let param = Some(parameter.name.as_str());
+    let error = |kind| CommandValidationError {
+        qualified_name: qualified_name.to_owned(),
+        parameter: Some(parameter.name.clone()),
+        kind,
+        __non_exhaustive: (),
+    };
+
+    check_name(errors, qualified_name, param, &parameter.name, None);
+    for (locale, name) in &parameter.name_localizations {
+        check_name(errors, qualified_name, param, name, Some(locale));
+    }
+    if let Some(description) = &parameter.description {
+        check_description(errors, qualified_name, param, description, None);
+    }
+    for (locale, description) in &parameter.description_localizations {
+        check_description(errors, qualified_name, param, description, Some(locale));
+    }
+
+    if parameter.choices.len() > MAX_CHOICES {
+        errors.push(error(CommandValidationErrorKind::TooManyChoices {
+            count: parameter.choices.len(),
+        }));
+    }
+    for choice in &parameter.choices {
+        let names = std::iter::once(&choice.name).chain(choice.localizations.values());
+        for name in names {
+            let length = name.chars().count();
+            if length == 0 || length > MAX_DESCRIPTION_LENGTH {
+                errors.push(error(CommandValidationErrorKind::InvalidChoiceName {
+                    name: name.clone(),
+                }));
+            }
+        }
+    }
+}
+
+/// Whether Discord accepts this character in a slash command or parameter name.
+///
+/// Mirrors the `^[-_\p{L}\p{N}\p{sc=Deva}\p{sc=Thai}]{1,32}$` pattern from Discord's docs
+fn is_valid_name_char(c: char) -> bool {
+    c == '-'
+        || c == '_'
+        || c.is_alphanumeric()
+        || ('\u{0900}'..='\u{097F}').contains(&c) // Devanagari
+        || ('\u{0E00}'..='\u{0E7F}').contains(&c) // Thai
+}
+
+/// Checks a slash command or parameter name for format, length and case
+fn check_name(
+    errors: &mut Vec<CommandValidationError>,
+    qualified_name: &str,
+    parameter: Option<&str>,
+    name: &str,
+    locale: Option<&String>,
+) {
+    let length = name.chars().count();
+    let kind = if length == 0 || length > MAX_NAME_LENGTH || !name.chars().all(is_valid_name_char) {
+        CommandValidationErrorKind::InvalidName {
+            name: name.to_owned(),
+            locale: locale.cloned(),
+        }
+    } else if name.to_lowercase() != name {
+        CommandValidationErrorKind::UppercaseName {
+            name: name.to_owned(),
+            locale: locale.cloned(),
+        }
+    } else {
+        return;
+    };
+
+    errors.push(CommandValidationError {
+        qualified_name: qualified_name.to_owned(),
+        parameter: parameter.map(|p| p.to_owned()),
+        kind,
+        __non_exhaustive: (),
+    });
+}
+
+/// Checks a slash command or parameter description for length
+fn check_description(
+    errors: &mut Vec<CommandValidationError>,
+    qualified_name: &str,
+    parameter: Option<&str>,
+    description: &str,
+    locale: Option<&String>,
+) {
+    let length = description.chars().count();
+    if length == 0 || length > MAX_DESCRIPTION_LENGTH {
+        errors.push(CommandValidationError {
+            qualified_name: qualified_name.to_owned(),
+            parameter: parameter.map(|p| p.to_owned()),
+            kind: CommandValidationErrorKind::InvalidDescriptionLength {
+                length,
+                locale: locale.cloned(),
+            },
+            __non_exhaustive: (),
+        });
+    }
+}
+
+/// Reports every name that occurs more than once among siblings.
+///
+/// If `are_parameters` is true, the names are parameters of the command `parent`. Otherwise,
+/// they're (sub)commands, and the duplicate is reported on the duplicated command itself.
+fn check_unique_names<'a>(
+    errors: &mut Vec<CommandValidationError>,
+    parent: &str,
+    are_parameters: bool,
+    names: impl Iterator<Item = &'a str>,
+) {
+    let mut seen = std::collections::HashSet::new();
+    let mut reported = std::collections::HashSet::new();
+    for name in names {
+        if seen.insert(name) || !reported.insert(name) {
+            continue;
+        }
+
+        let (qualified_name, parameter) = match (are_parameters, parent) {
+            (true, _) => (parent.to_owned(), Some(name.to_owned())),
+            (false, "") => (name.to_owned(), None),
+            (false, _) => (format!("{} {}", parent, name), None),
+        };
+        errors.push(CommandValidationError {
+            qualified_name,
+            parameter,
+            kind: CommandValidationErrorKind::DuplicateName {
+                name: name.to_owned(),
+            },
+            __non_exhaustive: (),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_validation() {
+        let mut errors = Vec::new();
+        for name in ["ping", "set-prefix", "user_info", "日本語", "नमस्ते", "x1"] {
+            check_name(&mut errors, name, None, name, None);
+        }
+        assert_eq!(errors, []);
+
+        for name in ["", "with space", "emoji🎉", &"a".repeat(33)] {
+            check_name(&mut errors, "cmd", None, name, None);
+        }
+        check_name(&mut errors, "cmd", Some("arg"), "Arg", None);
+        let kinds = errors.into_iter().map(|e| e.kind).collect::<Vec<_>>();
+        assert!(kinds[..4]
+            .iter()
+            .all(|k| matches!(k, CommandValidationErrorKind::InvalidName { .. })));
+        assert!(matches!(
+            kinds[4],
+            CommandValidationErrorKind::UppercaseName { .. }
+        ));
+    }
+
+    #[test]
+    fn test_validate_commands() {
+        let mut command = crate::Command::<(), ()> {
+            name: "config".into(),
+            slash_action: Some(|_| Box::pin(async { Ok(()) })),
+            ..Default::default()
+        };
+        let parameter = |name: &str, required| crate::CommandParameter::<(), ()> {
+            name: name.into(),
+            name_localizations: Default::default(),
+            description: Some("A parameter".into()),
+            description_localizations: Default::default(),
+            required,
+            channel_types: None,
+            choices: Vec::new(),
+            type_setter: None,
+            autocomplete_callback: None,
+            __non_exhaustive: (),
+        };
+        command.parameters = vec![
+            parameter("key", true),
+            parameter("value", false),
+            parameter("key", true),
+        ];
+
+        let errors = validate_commands(&[command]);
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            [
+                "command `config`, parameter `key`: name `key` is used more than once",
+                "command `config`, parameter `key`: required parameter comes after an optional \
+                 parameter",
+            ]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Middleware which wraps command invocations
+
+use crate::BoxFuture;
+use std::sync::Arc;
+
+/// Type of the function wrapped by [`Layer`]
+type LayerFn<U, E> = dyn for<'a> Fn(
+        crate::Context<'a, U, E>,
+        Next<'a, U, E>,
+    ) -> BoxFuture<'a, Result<(), crate::FrameworkError<'a, U, E>>>
+    + Send
+    + Sync;
+
+/// Middleware which runs around the command action, e.g. to open a database transaction and
+/// commit or roll it back depending on the outcome, or to time the command.
+///
+/// A layer receives the [`crate::Context`] and the rest of the chain as [`Next`], and decides if,
+/// when and how often to call [`Next::run`]. Not calling it skips the command. The result is what
+/// the command returned, or what inner layers made of it, and is passed on to
+/// [`crate::FrameworkOptions::on_error`] if it's an error.
+///
+/// Set via [`crate::FrameworkOptions::layers`], which wrap all commands, and
+/// [`crate::Command::layers`] or the `layer` command attribute. Framework layers run outermost,
+/// followed by layers of parent commands and finally the command's own layers. All of them run
+/// after checks and [`crate::FrameworkOptions::pre_command`], and within
+/// [`crate::Command::timeout`].
+///
+/// ```rust
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # type Context<'a> = poise::Context<'a, (), Error>;
+/// fn timing() -> poise::Layer<(), Error> {
+///     poise::Layer::new(|ctx, next| Box::pin(async move {
+///         let start = std::time::Instant::now();
+///         let result = next.run().await;
+///         println!("{} took {:?}", ctx.command().qualified_name, start.elapsed());
+///         result
+///     }))
+/// }
+///
+/// #[poise::command(prefix_command, layer = "timing")]
+/// async fn ping(ctx: Context<'_>) -> Result<(), Error> {
+///     ctx.say("Pong!").await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(derivative::Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct Layer<U, E> {
+    /// The layer function
+    #[derivative(Debug = "ignore")]
+    layer: Arc<LayerFn<U, E>>,
+}
+
+impl<U, E> Layer<U, E> {
+    /// Wraps a layer function
+    pub fn new(
+        layer: impl for<'a> Fn(
+                crate::Context<'a, U, E>,
+                Next<'a, U, E>,
+            ) -> BoxFuture<'a, Result<(), crate::FrameworkError<'a, U, E>>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            layer: Arc::new(layer),
+        }
+    }
+}
+
+/// The remaining layers and the command action, passed to each [`Layer`]
+pub struct Next<'a, U, E> {
+    /// General context
+    ctx: crate::Context<'a, U, E>,
+    /// Layers which haven't run yet, outermost first
+    layers: std::vec::IntoIter<&'a Layer<U, E>>,
+    /// The command action, which runs once all layers have been passed
+    action: BoxFuture<'a, Result<(), crate::FrameworkError<'a, U, E>>>,
+}
+
+impl<'a, U, E> Next<'a, U, E> {
+    /// Wraps the action of the invoked command in all layers that apply to it
+    pub(crate) fn new(
+        ctx: crate::Context<'a, U, E>,
+        action: BoxFuture<'a, Result<(), crate::FrameworkError<'a, U, E>>>,
+    ) -> Self {
+        let layers = ctx.framework().options().layers.iter();
+        let parent_layers = ctx.parent_commands().iter().flat_map(|c| &c.layers);
+        let layers = layers.chain(parent_layers).chain(&ctx.command().layers);
+        Self {
+            ctx,
+            layers: layers.collect::<Vec<_>>().into_iter(),
+            action,
+        }
+    }
+
+    /// Runs the next layer, or the command action if all layers have run
+    pub async fn run(mut self) -> Result<(), crate::FrameworkError<'a, U, E>> {
+        match self.layers.next() {
+            Some(layer) => (layer.layer)(self.ctx, self).await,
+            None => self.action.await,
+        }
+    }
+}
+
+impl<U, E> std::fmt::Debug for Next<'_, U, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Next")
+            .field("remaining_layers", &self.layers.len())
+            .finish_non_exhaustive()
+    }
+}
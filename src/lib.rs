@@ -381,6 +381,10 @@ fn test_calc() {
 }
 ```
 
+If you want to test your commands end to end anyway, enable the `testing` feature and have a look
+at the `poise::testing` module. It runs the framework against a local stand-in for the Discord API
+and records everything your commands send.
+
 # About the weird name
 I'm bad at names. Google lists "poise" as a synonym to "serenity" which is the Discord library
 underlying this framework, so that's what I chose.
@@ -388,16 +392,24 @@ underlying this framework, so that's what I chose.
 Also, poise is a stat in Dark Souls
 */
 
+pub mod blocklist;
 pub mod builtins;
+pub mod check;
 pub mod choice_parameter;
+pub mod concurrency;
 pub mod cooldown;
 pub mod dispatch;
 pub mod framework;
+pub mod layer;
+pub mod metrics;
 pub mod modal;
 pub mod prefix_argument;
+pub mod registry;
 pub mod reply;
 pub mod slash_argument;
 pub mod structs;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod track_edits;
 mod util;
 pub mod macros {
@@ -408,8 +420,9 @@ pub mod macros {
 
 #[doc(no_inline)]
 pub use {
-    choice_parameter::*, cooldown::*, dispatch::*, framework::*, macros::*, modal::*,
-    prefix_argument::*, reply::*, slash_argument::*, structs::*, track_edits::*,
+    blocklist::*, check::*, choice_parameter::*, concurrency::*, cooldown::*, dispatch::*,
+    framework::*, layer::*, macros::*, metrics::*, modal::*, prefix_argument::*, registry::*,
+    reply::*, slash_argument::*, structs::*, track_edits::*,
 };
 
 /// See [`builtins`]
@@ -0,0 +1,223 @@
+//! Infrastructure for collecting command and dispatch metrics
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Upper bounds of the buckets of a [`Histogram`], except for the last bucket which is unbounded
+pub const HISTOGRAM_BOUNDS: [Duration; 11] = [
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(25),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_millis(1000),
+    Duration::from_millis(2500),
+    Duration::from_millis(5000),
+    Duration::from_millis(10000),
+];
+
+/// Distribution of durations, bucketed by [`HISTOGRAM_BOUNDS`]
+///
+/// ```rust
+/// # use std::time::Duration;
+/// let mut latency = poise::Histogram::default();
+/// latency.record(Duration::from_millis(3));
+/// latency.record(Duration::from_millis(40));
+/// assert_eq!(latency.count, 2);
+/// assert_eq!(latency.quantile(0.5), Some(Duration::from_millis(5)));
+/// assert_eq!(latency.quantile(1.0), Some(Duration::from_millis(40)));
+/// ```
+#[derive(Default, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Histogram {
+    /// Number of durations per bucket. The last bucket counts durations above all bounds
+    pub buckets: [u64; HISTOGRAM_BOUNDS.len() + 1],
+    /// Number of recorded durations
+    pub count: u64,
+    /// Sum of all recorded durations
+    pub sum: Duration,
+    /// Longest recorded duration
+    pub max: Duration,
+}
+
+impl Histogram {
+    /// Adds a duration to the distribution
+    pub fn record(&mut self, duration: Duration) {
+        let bucket = HISTOGRAM_BOUNDS
+            .iter()
+            .position(|bound| duration <= *bound)
+            .unwrap_or(HISTOGRAM_BOUNDS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum += duration;
+        self.max = self.max.max(duration);
+    }
+
+    /// Average of all recorded durations, or None if there are none
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(
+            self.sum.as_secs_f64() / self.count as f64,
+        ))
+    }
+
+    /// Estimates the given quantile, e.g. 0.95 for the 95th percentile, as the upper bound of the
+    /// bucket it falls into. Returns None if there are no recorded durations
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = HISTOGRAM_BOUNDS.get(bucket).copied();
+                return Some(bound.map_or(self.max, |bound| bound.min(self.max)));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+/// Metrics of a single command, see [`MetricsSnapshot::commands`]
+#[derive(Default, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct CommandMetrics {
+    /// Number of invocations, regardless of outcome
+    pub invocations: u64,
+    /// Number of invocations which didn't end with [`crate::InvocationOutcome::Success`]
+    pub failures: u64,
+    /// Number of invocations whose arguments couldn't be parsed
+    pub argument_parse_failures: u64,
+    /// Number of invocations which hit a cooldown
+    pub cooldown_hits: u64,
+    /// Duration of invocations, from the checks to the end of the command
+    pub latency: Histogram,
+    /// Duration of autocomplete callbacks of the command's parameters
+    pub autocomplete_latency: Histogram,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+/// All metrics collected by a [`MetricsSink`] at one point in time
+#[derive(Default, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct MetricsSnapshot {
+    /// Metrics per command, by [`crate::Command::qualified_name`]
+    pub commands: BTreeMap<String, CommandMetrics>,
+    /// Number of errors per [`crate::FrameworkError`] variant, see
+    /// [`crate::FrameworkError::variant_name`]
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Number of tracked messages in [`crate::PrefixFrameworkOptions::edit_tracker`], as of the
+    /// last change
+    pub edit_tracker_size: usize,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+/// Receives metrics about command invocations and dispatch, set via
+/// [`crate::FrameworkOptions::metrics`].
+///
+/// The default is [`InMemoryMetricsSink`]. Implement this trait to forward the metrics to an
+/// external system, e.g. Prometheus. All methods do nothing by default, so only implement the ones
+/// you're interested in.
+///
+/// The methods are called inline during dispatch, so they shouldn't block.
+#[allow(unused_variables)] // for the default method implementations
+pub trait MetricsSink: Send + Sync {
+    /// Called after every command invocation which wasn't ignored, see
+    /// [`crate::FrameworkOptions::on_invocation_finished`]
+    fn record_invocation(
+        &self,
+        command: &str,
+        outcome: crate::InvocationOutcome,
+        duration: Duration,
+    ) {
+    }
+
+    /// Called for every [`crate::FrameworkError`] that is handled, with its
+    /// [`crate::FrameworkError::variant_name`]
+    fn record_error(&self, variant: &'static str) {}
+
+    /// Called after every autocomplete callback
+    fn record_autocomplete(&self, command: &str, duration: Duration) {}
+
+    /// Called whenever the number of tracked messages in
+    /// [`crate::PrefixFrameworkOptions::edit_tracker`] may have changed
+    fn record_edit_tracker_size(&self, size: usize) {}
+
+    /// Returns all collected metrics, e.g. for [`crate::builtins::metrics`]. Returns None by
+    /// default, for sinks which don't keep the metrics themselves
+    fn snapshot(&self) -> Option<MetricsSnapshot> {
+        None
+    }
+}
+
+/// The default [`MetricsSink`], which keeps all metrics in memory
+///
+/// Metrics are kept per command, so memory usage is bounded by the number of commands.
+#[derive(Default, Debug)]
+pub struct InMemoryMetricsSink {
+    /// All metrics collected so far
+    metrics: std::sync::Mutex<MetricsSnapshot>,
+}
+
+impl InMemoryMetricsSink {
+    /// Creates a sink without any metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MetricsSink for InMemoryMetricsSink {
+    fn record_invocation(
+        &self,
+        command: &str,
+        outcome: crate::InvocationOutcome,
+        duration: Duration,
+    ) {
+        let mut metrics = self.metrics.lock().unwrap();
+        let command = metrics.commands.entry(command.to_owned()).or_default();
+        command.invocations += 1;
+        match outcome {
+            crate::InvocationOutcome::Success => {}
+            crate::InvocationOutcome::ArgumentParse => {
+                command.failures += 1;
+                command.argument_parse_failures += 1;
+            }
+            crate::InvocationOutcome::Cooldown => {
+                command.failures += 1;
+                command.cooldown_hits += 1;
+            }
+            _ => command.failures += 1,
+        }
+        command.latency.record(duration);
+    }
+
+    fn record_error(&self, variant: &'static str) {
+        *self
+            .metrics
+            .lock()
+            .unwrap()
+            .errors
+            .entry(variant)
+            .or_default() += 1;
+    }
+
+    fn record_autocomplete(&self, command: &str, duration: Duration) {
+        let mut metrics = self.metrics.lock().unwrap();
+        let command = metrics.commands.entry(command.to_owned()).or_default();
+        command.autocomplete_latency.record(duration);
+    }
+
+    fn record_edit_tracker_size(&self, size: usize) {
+        self.metrics.lock().unwrap().edit_tracker_size = size;
+    }
+
+    fn snapshot(&self) -> Option<MetricsSnapshot> {
+        Some(self.metrics.lock().unwrap().clone())
+    }
+}
@@ -0,0 +1,236 @@
+//! Infrastructure for changing the available commands while the bot is running
+
+use crate::serenity_prelude as serenity;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// The changes made to the top-level commands at one point in time. Replaced as a whole on every
+/// change, so that dispatch can keep using a consistent view without holding a lock
+#[derive(derivative::Derivative)]
+#[derivative(Default(bound = ""), Clone(bound = ""))]
+struct Snapshot<U, E> {
+    /// Commands added at runtime
+    added: Vec<Arc<crate::Command<U, E>>>,
+    /// Names of removed top-level commands, to hide them in
+    /// [`crate::FrameworkOptions::commands`]
+    removed: HashSet<String>,
+}
+
+/// Lets you enable, disable, add and remove commands while the bot is running, e.g. to disable a
+/// broken command during an incident or to load commands of a plugin. Stored in
+/// [`crate::FrameworkOptions::registry`].
+///
+/// Disabled commands stay known to the framework, but invoking them raises
+/// [`crate::FrameworkError::CommandDisabled`]. Disabling a command disables its subcommands too.
+///
+/// All changes apply to the next invocation. Application commands must be registered on Discord
+/// again after adding or removing commands, see [`crate::builtins::sync_application_commands`].
+/// Removed commands are freed once the invocations still using them have finished.
+///
+/// ```rust,no_run
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// # type Context<'a> = poise::Context<'a, (), Error>;
+/// # #[poise::command(slash_command)]
+/// # async fn weather(ctx: Context<'_>) -> Result<(), Error> { Ok(()) }
+/// # async fn foo(ctx: Context<'_>) -> Result<(), Error> {
+/// let registry = ctx.framework().registry();
+/// registry.disable("ping", ctx.guild_id());
+/// registry.remove("roll");
+/// registry.add(weather());
+///
+/// let commands = ctx.framework().options().active_commands();
+/// poise::builtins::sync_application_commands(ctx, &commands, None).await?;
+/// # Ok(()) }
+/// ```
+#[derive(derivative::Derivative)]
+#[derivative(Default(bound = ""), Debug(bound = ""))]
+pub struct CommandRegistry<U, E> {
+    /// Commands added and removed at runtime
+    #[derivative(Debug = "ignore")]
+    snapshot: RwLock<Arc<Snapshot<U, E>>>,
+    /// Qualified names of disabled commands by the guild they're disabled in, or None for commands
+    /// disabled globally
+    disabled: RwLock<HashMap<Option<serenity::GuildId>, HashSet<String>>>,
+}
+
+impl<U, E> CommandRegistry<U, E> {
+    /// Creates a registry without any changes to [`crate::FrameworkOptions::commands`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a top-level command, replacing any command of the same name
+    pub fn add(&self, mut command: crate::Command<U, E>) {
+        crate::set_qualified_names(std::slice::from_mut(&mut command));
+        for error in crate::validate_commands(std::slice::from_ref(&command)) {
+            tracing::warn!("Invalid command: {error}");
+        }
+
+        let mut snapshot = self.snapshot.write().unwrap();
+        // Only copies the snapshot if a dispatch is still using it
+        let snapshot = Arc::make_mut(&mut snapshot);
+        snapshot.removed.insert(command.name.clone());
+        snapshot.added.retain(|added| added.name != command.name);
+        snapshot.added.push(Arc::new(command));
+    }
+
+    /// Removes the top-level command of the given name, including its subcommands. Does nothing if
+    /// there's no such command
+    pub fn remove(&self, name: &str) {
+        let mut snapshot = self.snapshot.write().unwrap();
+        let snapshot = Arc::make_mut(&mut snapshot);
+        snapshot.removed.insert(name.to_owned());
+        snapshot.added.retain(|added| added.name != name);
+    }
+
+    /// Disables the command with the given [`crate::Command::qualified_name`] in the given guild,
+    /// or everywhere if None
+    pub fn disable(&self, qualified_name: &str, guild_id: Option<serenity::GuildId>) {
+        let mut disabled = self.disabled.write().unwrap();
+        disabled
+            .entry(guild_id)
+            .or_default()
+            .insert(qualified_name.to_owned());
+    }
+
+    /// Reverts [`Self::disable`] with the same arguments. Enabling a command in a guild has no
+    /// effect while it's disabled everywhere
+    pub fn enable(&self, qualified_name: &str, guild_id: Option<serenity::GuildId>) {
+        let mut disabled = self.disabled.write().unwrap();
+        if let Some(names) = disabled.get_mut(&guild_id) {
+            names.remove(qualified_name);
+            if names.is_empty() {
+                disabled.remove(&guild_id);
+            }
+        }
+    }
+
+    /// Whether the command with the given [`crate::Command::qualified_name`] is enabled in the
+    /// given guild, or in DMs if None. Doesn't take parent commands into account
+    pub fn is_enabled(&self, qualified_name: &str, guild_id: Option<serenity::GuildId>) -> bool {
+        let disabled = self.disabled.read().unwrap();
+        let is_disabled = |guild_id| {
+            disabled
+                .get(&guild_id)
+                .is_some_and(|names| names.contains(qualified_name))
+        };
+        !is_disabled(None) && !guild_id.is_some_and(|guild_id| is_disabled(Some(guild_id)))
+    }
+
+    /// Combines the commands given at startup with the changes made at runtime, see
+    /// [`crate::FrameworkOptions::active_commands`]
+    pub(crate) fn active_commands<'a>(
+        &self,
+        commands: &'a [crate::Command<U, E>],
+    ) -> ActiveCommands<'a, U, E> {
+        ActiveCommands {
+            initial: commands,
+            snapshot: self.snapshot.read().unwrap().clone(),
+        }
+    }
+}
+
+/// The top-level commands available at one point in time, returned by
+/// [`crate::FrameworkOptions::active_commands`]. Iterate over a reference to get the commands.
+///
+/// Keeps the commands added via [`CommandRegistry`] alive, even if they're removed in the
+/// meantime. Changes to the registry don't affect existing instances.
+#[derive(derivative::Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct ActiveCommands<'a, U, E> {
+    /// [`crate::FrameworkOptions::commands`]
+    initial: &'a [crate::Command<U, E>],
+    /// Changes made at runtime
+    snapshot: Arc<Snapshot<U, E>>,
+}
+
+impl<U, E> ActiveCommands<'_, U, E> {
+    /// Iterates over the commands: [`crate::FrameworkOptions::commands`] without the removed ones,
+    /// followed by the added ones
+    pub fn iter(&self) -> ActiveCommandsIter<'_, U, E> {
+        ActiveCommandsIter {
+            initial: self.initial.iter(),
+            added: self.snapshot.added.iter(),
+            removed: &self.snapshot.removed,
+        }
+    }
+}
+
+impl<'b, U, E> IntoIterator for &'b ActiveCommands<'_, U, E> {
+    type Item = &'b crate::Command<U, E>;
+    type IntoIter = ActiveCommandsIter<'b, U, E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over [`ActiveCommands`]
+pub struct ActiveCommandsIter<'a, U, E> {
+    /// Remaining commands given at startup
+    initial: std::slice::Iter<'a, crate::Command<U, E>>,
+    /// Remaining commands added at runtime
+    added: std::slice::Iter<'a, Arc<crate::Command<U, E>>>,
+    /// Names of the commands given at startup to skip
+    removed: &'a HashSet<String>,
+}
+
+impl<'a, U, E> Iterator for ActiveCommandsIter<'a, U, E> {
+    type Item = &'a crate::Command<U, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let removed = self.removed;
+        match self
+            .initial
+            .find(|command| !removed.contains(&command.name))
+        {
+            Some(command) => Some(command),
+            None => self.added.next().map(|command| &**command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(name: &str) -> crate::Command<(), ()> {
+        crate::Command {
+            name: name.to_owned(),
+            qualified_name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_add_remove_and_disable() {
+        let initial = vec![command("a"), command("b")];
+        let registry = CommandRegistry::new();
+        let names = |registry: &CommandRegistry<(), ()>| {
+            let commands = registry.active_commands(&initial);
+            commands.iter().map(|c| c.name.clone()).collect::<Vec<_>>()
+        };
+
+        registry.add(command("c"));
+        registry.remove("a");
+        registry.add(command("b"));
+        assert_eq!(names(&registry), ["c", "b"]);
+        let commands = registry.active_commands(&initial);
+        registry.remove("c");
+        assert_eq!(names(&registry), ["b"]);
+
+        // Removed commands stay alive while in use
+        let removed = Arc::downgrade(&commands.snapshot.added[0]);
+        assert_eq!(commands.iter().next().unwrap().name, "c");
+        drop(commands);
+        assert!(removed.upgrade().is_none());
+
+        let guild_id = serenity::GuildId::new(1);
+        registry.disable("b", Some(guild_id));
+        assert!(!registry.is_enabled("b", Some(guild_id)));
+        assert!(registry.is_enabled("b", None));
+        registry.disable("b", None);
+        registry.enable("b", Some(guild_id));
+        assert!(!registry.is_enabled("b", Some(guild_id)));
+    }
+}
@@ -58,10 +58,25 @@ pub struct Command<U, E> {
     /// Multiline description with detailed usage instructions. Displayed in the command specific
     /// help: `~help command_name`
     pub help_text: Option<String>,
-    /// Handles command cooldowns. Mainly for framework internal use
+    /// Cooldown tracker for custom cooldown handling with
+    /// [`crate::FrameworkOptions::manual_cooldowns`]. The automatic cooldown handling stores its
+    /// state in [`crate::FrameworkOptions::cooldown_store`] instead
     pub cooldowns: std::sync::Mutex<crate::CooldownTracker>,
-    /// Configuration for the [`crate::CooldownTracker`]
+    /// Cooldown configuration, used by the automatic cooldown handling. Can be overridden at
+    /// runtime via [`crate::FrameworkOptions::dynamic_cooldown_config`]
     pub cooldown_config: std::sync::RwLock<crate::CooldownConfig>,
+    /// If set, this command shares its cooldown history with all other commands in the same
+    /// cooldown group, instead of tracking it by [`Self::identifying_name`].
+    ///
+    /// Groups are stored separately from commands in [`crate::FrameworkOptions::cooldown_store`],
+    /// see [`Self::cooldown_key`], so a group may be named after one of its commands. Each command
+    /// still checks the shared history against its own [`Self::cooldown_config`], so commands in a
+    /// group should usually have the same config.
+    pub cooldown_group: Option<String>,
+    /// If set, limits how many invocations of this command may run at the same time
+    pub max_concurrency: Option<crate::MaxConcurrency>,
+    /// Running invocations of this command, used to enforce [`Self::max_concurrency`]
+    pub concurrency: crate::ConcurrencyTracker,
     /// After the first response, whether to post subsequent responses as edits to the initial
     /// message
     ///
@@ -93,12 +108,31 @@ pub struct Command<U, E> {
     pub dm_only: bool,
     /// If true, the command may only run in NSFW channels
     pub nsfw_only: bool,
+    /// If not empty, the command may only run in these kinds of channels
+    pub channel_kinds: Vec<ChannelKind>,
+    /// The invoking member must have all of these roles. Not satisfiable in DMs
+    pub required_roles: Vec<RoleIdentifier>,
+    /// If not empty, the invoking member must have at least one of these roles. Not satisfiable in
+    /// DMs
+    pub any_role: Vec<RoleIdentifier>,
+    /// The invoking member must have none of these roles
+    pub forbidden_roles: Vec<RoleIdentifier>,
     /// Command-specific override for [`crate::FrameworkOptions::on_error`]
     #[derivative(Debug = "ignore")]
     pub on_error: Option<fn(crate::FrameworkError<'_, U, E>) -> BoxFuture<'_, ()>>,
+    /// Command-specific override for [`crate::FrameworkOptions::command_timeout`]
+    pub timeout: Option<std::time::Duration>,
     /// If any of these functions returns false, this command will not be executed.
+    ///
+    /// Checks of parent commands also apply to their subcommands, and run first.
     #[derivative(Debug = "ignore")]
     pub checks: Vec<fn(crate::Context<'_, U, E>) -> BoxFuture<'_, Result<bool, E>>>,
+    /// Like [`Self::checks`], but the checks can give a reason when denying access and can be
+    /// combined. Run after [`Self::checks`]
+    pub structured_checks: Vec<crate::Check<U, E>>,
+    /// Middleware which runs around this command's action, and that of its subcommands. Runs
+    /// within [`crate::FrameworkOptions::layers`]. See [`crate::Layer`]
+    pub layers: Vec<crate::Layer<U, E>>,
     /// List of parameters for this command
     ///
     /// Used for registering and parsing slash commands. Can also be used in help commands
@@ -235,4 +269,107 @@ impl<U, E> Command<U, E> {
 
         Some(builder)
     }
+
+    /// Returns the name under which the automatic cooldown handling stores this command's cooldown
+    /// history in [`crate::FrameworkOptions::cooldown_store`]: `group:` followed by
+    /// [`Self::cooldown_group`] if set, else [`Self::identifying_name`]
+    pub fn cooldown_key(&self) -> std::borrow::Cow<'_, str> {
+        match &self.cooldown_group {
+            Some(cooldown_group) => format!("group:{}", cooldown_group).into(),
+            None => self.identifying_name.as_str().into(),
+        }
+    }
+
+    /// Returns the cooldown configuration which applies to this command in the given context.
+    ///
+    /// That's the one returned by [`crate::FrameworkOptions::dynamic_cooldown_config`] if set,
+    /// falling back to [`Self::cooldown_config`].
+    pub async fn resolve_cooldown_config(
+        &self,
+        ctx: crate::PartialContext<'_, U, E>,
+    ) -> Result<crate::CooldownConfig, E> {
+        if let Some(dynamic_cooldown_config) = ctx.framework.options.dynamic_cooldown_config {
+            if let Some(config) = dynamic_cooldown_config(ctx, self).await? {
+                return Ok(config);
+            }
+        }
+        Ok(self.cooldown_config.read().unwrap().clone())
+    }
+}
+
+/// Refers to a guild role by ID or by name, e.g. in [`Command::required_roles`]
+///
+/// Names are matched exactly against the roles of the guild the command was invoked in.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum RoleIdentifier {
+    /// The role with this ID
+    Id(serenity::RoleId),
+    /// The role with this name
+    Name(String),
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+impl From<serenity::RoleId> for RoleIdentifier {
+    fn from(role_id: serenity::RoleId) -> Self {
+        Self::Id(role_id)
+    }
+}
+
+impl From<&str> for RoleIdentifier {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_owned())
+    }
+}
+
+impl From<String> for RoleIdentifier {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl std::fmt::Display for RoleIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Id(role_id) => write!(f, "<@&{}>", role_id),
+            Self::Name(name) => f.write_str(name),
+            Self::__NonExhaustive => unreachable!(),
+        }
+    }
+}
+
+/// A kind of channel a command may be restricted to, see [`Command::channel_kinds`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ChannelKind {
+    /// Direct messages
+    Dm,
+    /// Regular guild text channels
+    Text,
+    /// Announcement channels
+    News,
+    /// The text chat of voice channels
+    Voice,
+    /// The text chat of stage channels
+    Stage,
+    /// Any thread, including forum posts
+    Thread,
+    /// Posts in forum channels
+    ForumPost,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+impl std::fmt::Display for ChannelKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Dm => "DMs",
+            Self::Text => "text channels",
+            Self::News => "announcement channels",
+            Self::Voice => "voice channels",
+            Self::Stage => "stage channels",
+            Self::Thread => "threads",
+            Self::ForumPost => "forum posts",
+            Self::__NonExhaustive => unreachable!(),
+        })
+    }
 }
@@ -0,0 +1,396 @@
+//! Holds handler structs for message component (button, select menu) and modal interactions.
+
+use crate::{serenity_prelude as serenity, BoxFuture};
+
+/// Possible ways to match the custom ID of a message component or modal interaction
+#[derive(Clone, Debug)]
+pub enum CustomIdPattern {
+    /// A case-sensitive string literal that the custom ID must start with (passed to
+    /// [`str::strip_prefix`])
+    Prefix(&'static str),
+    /// Regular expression which must match at the start of the custom ID
+    Regex(regex::Regex),
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+impl CustomIdPattern {
+    /// If the given custom ID matches this pattern, returns the rest of the custom ID after the
+    /// matched part
+    pub fn strip<'a>(&self, custom_id: &'a str) -> Option<&'a str> {
+        match self {
+            &Self::Prefix(prefix) => custom_id.strip_prefix(prefix),
+            Self::Regex(regex) => {
+                let regex_match = regex.find(custom_id)?;
+                if regex_match.start() == 0 {
+                    Some(&custom_id[regex_match.end()..])
+                } else {
+                    None
+                }
+            }
+            Self::__NonExhaustive => unreachable!(),
+        }
+    }
+}
+
+/// A handler for message component interactions, registered in
+/// [`crate::FrameworkOptions::component_handlers`]
+///
+/// Component interactions are routed to the first handler whose [`Self::custom_id`] pattern
+/// matches. Since handlers are looked up by custom ID on every interaction, buttons keep working
+/// across bot restarts, unlike collectors.
+///
+/// ```rust
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// async fn vote(ctx: poise::ComponentContext<'_, (), Error>) -> Result<(), Error> {
+///     // For a custom ID of "vote:yes", `ctx.args` is "yes"
+///     ctx.say(format!("You voted {}", ctx.args)).await?;
+///     Ok(())
+/// }
+///
+/// let options = poise::FrameworkOptions::<(), Error> {
+///     component_handlers: vec![poise::ComponentHandler::new(
+///         poise::CustomIdPattern::Prefix("vote:"),
+///         |ctx| Box::pin(vote(ctx)),
+///     )],
+///     ..Default::default()
+/// };
+/// ```
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct ComponentHandler<U, E> {
+    /// Which custom IDs this handler is responsible for
+    pub custom_id: CustomIdPattern,
+    /// Callback to execute when a matching component interaction is received
+    #[derivative(Debug = "ignore")]
+    pub action: fn(ComponentContext<'_, U, E>) -> BoxFuture<'_, Result<(), E>>,
+    // #[non_exhaustive] forbids struct update syntax for ?? reason
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl<U, E> ComponentHandler<U, E> {
+    /// Creates a new handler for the given custom ID pattern
+    pub fn new(
+        custom_id: CustomIdPattern,
+        action: fn(ComponentContext<'_, U, E>) -> BoxFuture<'_, Result<(), E>>,
+    ) -> Self {
+        Self {
+            custom_id,
+            action,
+            __non_exhaustive: (),
+        }
+    }
+}
+
+/// Context passed to [`ComponentHandler`] callbacks
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct ComponentContext<'a, U, E> {
+    /// Serenity's context, like HTTP or cache
+    #[derivative(Debug = "ignore")]
+    pub serenity_context: &'a serenity::Context,
+    /// The interaction which triggered this handler
+    pub interaction: &'a serenity::ComponentInteraction,
+    /// The rest of the custom ID after the part matched by [`ComponentHandler::custom_id`]
+    pub args: &'a str,
+    /// Keeps track of whether an initial response has been sent.
+    ///
+    /// Discord requires different HTTP endpoints for initial and additional responses.
+    pub has_sent_initial_response: &'a std::sync::atomic::AtomicBool,
+    /// Read-only reference to the framework
+    #[derivative(Debug = "ignore")]
+    pub framework: crate::FrameworkContext<'a, U, E>,
+    /// Your custom user data
+    #[derivative(Debug = "ignore")]
+    pub data: &'a U,
+    // #[non_exhaustive] forbids struct update syntax for ?? reason
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+impl<U, E> Clone for ComponentContext<'_, U, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<U, E> Copy for ComponentContext<'_, U, E> {}
+
+/// A handler for modal submissions, registered in [`crate::FrameworkOptions::modal_handlers`]
+///
+/// Modal submissions are routed to the first handler whose [`Self::custom_id`] pattern matches.
+/// The submission is parsed via [`crate::Modal::parse`] before being passed to the handler; parse
+/// failures are reported as [`crate::FrameworkError::ModalParse`].
+///
+/// Unlike [`crate::execute_modal`], this doesn't wait for the submission in a collector, so the
+/// modal can be submitted at any time, even after a bot restart. To open such a modal, send
+/// [`crate::Modal::create`] with a custom ID matching the handler's pattern.
+///
+/// ```rust
+/// # type Error = Box<dyn std::error::Error + Send + Sync>;
+/// #[derive(poise::Modal)]
+/// struct Feedback {
+///     text: String,
+/// }
+///
+/// async fn feedback(
+///     ctx: poise::ModalContext<'_, (), Error>,
+///     modal: Feedback,
+/// ) -> Result<(), Error> {
+///     ctx.say(format!("Thanks for your feedback: {}", modal.text)).await?;
+///     Ok(())
+/// }
+///
+/// let options = poise::FrameworkOptions::<(), Error> {
+///     modal_handlers: vec![poise::ModalHandler::new(
+///         poise::CustomIdPattern::Prefix("feedback"),
+///         |ctx, modal| Box::pin(feedback(ctx, modal)),
+///     )],
+///     ..Default::default()
+/// };
+/// ```
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct ModalHandler<U, E> {
+    /// Which custom IDs this handler is responsible for
+    pub custom_id: CustomIdPattern,
+    /// Parses the modal submission and calls the user callback
+    #[derivative(Debug = "ignore")]
+    pub action: Box<
+        dyn Send
+            + Sync
+            + for<'a> Fn(
+                ModalContext<'a, U, E>,
+            ) -> BoxFuture<'a, Result<(), crate::FrameworkError<'a, U, E>>>,
+    >,
+    // #[non_exhaustive] forbids struct update syntax for ?? reason
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl<U: Send + Sync + 'static, E: Send + 'static> ModalHandler<U, E> {
+    /// Creates a new handler which parses submissions into `M` and passes them to `action`
+    pub fn new<M: crate::Modal + Send + 'static>(
+        custom_id: CustomIdPattern,
+        action: for<'a> fn(ModalContext<'a, U, E>, M) -> BoxFuture<'a, Result<(), E>>,
+    ) -> Self {
+        Self {
+            custom_id,
+            action: Box::new(move |ctx| {
+                Box::pin(async move {
+                    let modal = M::parse(ctx.interaction.data.clone())
+                        .map_err(|error| crate::FrameworkError::ModalParse { error, ctx })?;
+                    action(ctx, modal)
+                        .await
+                        .map_err(|error| crate::FrameworkError::ModalHandler { error, ctx })
+                })
+            }),
+            __non_exhaustive: (),
+        }
+    }
+}
+
+/// Context passed to [`ModalHandler`] callbacks
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct ModalContext<'a, U, E> {
+    /// Serenity's context, like HTTP or cache
+    #[derivative(Debug = "ignore")]
+    pub serenity_context: &'a serenity::Context,
+    /// The modal submission which triggered this handler
+    pub interaction: &'a serenity::ModalInteraction,
+    /// The rest of the custom ID after the part matched by [`ModalHandler::custom_id`]
+    pub args: &'a str,
+    /// Keeps track of whether an initial response has been sent.
+    ///
+    /// Discord requires different HTTP endpoints for initial and additional responses.
+    pub has_sent_initial_response: &'a std::sync::atomic::AtomicBool,
+    /// Read-only reference to the framework
+    #[derivative(Debug = "ignore")]
+    pub framework: crate::FrameworkContext<'a, U, E>,
+    /// Your custom user data
+    #[derivative(Debug = "ignore")]
+    pub data: &'a U,
+    // #[non_exhaustive] forbids struct update syntax for ?? reason
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+impl<U, E> Clone for ModalContext<'_, U, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<U, E> Copy for ModalContext<'_, U, E> {}
+
+/// Generates the methods shared by [`ComponentContext`] and [`ModalContext`], whose interaction
+/// types have identically named fields and response methods
+macro_rules! interaction_context_methods {
+    ($type:ident) => {
+        impl<'a, U, E> $type<'a, U, E> {
+            /// Return the stored [`serenity::Context`]
+            pub fn serenity_context(self) -> &'a serenity::Context {
+                self.serenity_context
+            }
+
+            /// Returns a view into data stored by the framework, like configuration
+            pub fn framework(self) -> crate::FrameworkContext<'a, U, E> {
+                self.framework
+            }
+
+            /// Return a reference to your custom user data
+            pub fn data(self) -> &'a U {
+                self.data
+            }
+
+            /// The full custom ID of the component or modal
+            pub fn custom_id(self) -> &'a str {
+                &self.interaction.data.custom_id
+            }
+
+            /// Return the channel ID of this interaction
+            pub fn channel_id(self) -> serenity::ChannelId {
+                self.interaction.channel_id
+            }
+
+            /// Returns the guild ID of this interaction, if not in DMs
+            pub fn guild_id(self) -> Option<serenity::GuildId> {
+                self.interaction.guild_id
+            }
+
+            /// Return the user who triggered the interaction
+            pub fn author(self) -> &'a serenity::User {
+                &self.interaction.user
+            }
+
+            /// Acknowledge the interaction without sending a message, giving the bot multiple
+            /// minutes to respond without the user seeing an "interaction failed" error.
+            ///
+            /// Subsequent [`Self::send`] calls will send followup messages and
+            /// [`Self::update_message`] calls will edit the message the component is attached to.
+            pub async fn defer(self) -> Result<(), serenity::Error> {
+                if !self
+                    .has_sent_initial_response
+                    .load(std::sync::atomic::Ordering::SeqCst)
+                {
+                    self.interaction
+                        .create_response(
+                            self.serenity_context,
+                            serenity::CreateInteractionResponse::Acknowledge,
+                        )
+                        .await?;
+                    self.has_sent_initial_response
+                        .store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+                Ok(())
+            }
+
+            /// Shorthand of [`Self::send`] with just message content
+            pub async fn say(self, text: impl Into<String>) -> Result<(), serenity::Error> {
+                self.send(crate::CreateReply::default().content(text)).await
+            }
+
+            /// Sends a new message in response to the interaction. If a response to this
+            /// interaction has already been sent, a followup is sent.
+            pub async fn send(self, builder: crate::CreateReply) -> Result<(), serenity::Error> {
+                let builder = self.reply_builder(builder);
+
+                if self
+                    .has_sent_initial_response
+                    .load(std::sync::atomic::Ordering::SeqCst)
+                {
+                    let builder = builder.to_slash_followup_response(
+                        serenity::CreateInteractionResponseFollowup::new(),
+                    );
+                    self.interaction
+                        .create_followup(self.serenity_context, builder)
+                        .await?;
+                } else {
+                    let builder = builder.to_slash_initial_response(
+                        serenity::CreateInteractionResponseMessage::new(),
+                    );
+                    self.interaction
+                        .create_response(
+                            self.serenity_context,
+                            serenity::CreateInteractionResponse::Message(builder),
+                        )
+                        .await?;
+                    self.has_sent_initial_response
+                        .store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+                Ok(())
+            }
+
+            /// Edits the message which the component is attached to, e.g. to disable a button
+            /// after it has been pressed.
+            ///
+            /// For modals, this only works if the modal was opened in response to a component
+            /// interaction.
+            pub async fn update_message(
+                self,
+                builder: crate::CreateReply,
+            ) -> Result<(), serenity::Error> {
+                let builder = self.reply_builder(builder);
+
+                if self
+                    .has_sent_initial_response
+                    .load(std::sync::atomic::Ordering::SeqCst)
+                {
+                    let builder = builder
+                        .to_slash_initial_response_edit(serenity::EditInteractionResponse::new());
+                    self.interaction
+                        .edit_response(self.serenity_context, builder)
+                        .await?;
+                } else {
+                    let builder = builder.to_slash_initial_response(
+                        serenity::CreateInteractionResponseMessage::new(),
+                    );
+                    self.interaction
+                        .create_response(
+                            self.serenity_context,
+                            serenity::CreateInteractionResponse::UpdateMessage(builder),
+                        )
+                        .await?;
+                    self.has_sent_initial_response
+                        .store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+                Ok(())
+            }
+
+            /// Fills in [`crate::FrameworkOptions::allowed_mentions`] if not set by the builder
+            fn reply_builder(self, mut builder: crate::CreateReply) -> crate::CreateReply {
+                let fw_options = self.framework.options;
+                builder.allowed_mentions = builder
+                    .allowed_mentions
+                    .or_else(|| fw_options.allowed_mentions.clone());
+                builder
+            }
+        }
+    };
+}
+interaction_context_methods!(ComponentContext);
+interaction_context_methods!(ModalContext);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_id_pattern() {
+        let prefix = CustomIdPattern::Prefix("vote:");
+        assert_eq!(prefix.strip("vote:yes"), Some("yes"));
+        assert_eq!(prefix.strip("vote:"), Some(""));
+        assert_eq!(prefix.strip("Vote:yes"), None);
+        assert_eq!(prefix.strip("revote:yes"), None);
+
+        // Exact match
+        let exact = CustomIdPattern::Regex(regex::Regex::new("confirm$").unwrap());
+        assert_eq!(exact.strip("confirm"), Some(""));
+        assert_eq!(exact.strip("confirm:1"), None);
+        assert_eq!(exact.strip("unconfirm"), None);
+
+        // Variable segments are consumed by the match, the rest is passed on
+        let segments = CustomIdPattern::Regex(regex::Regex::new("page:[0-9]+:").unwrap());
+        assert_eq!(segments.strip("page:12:next"), Some("next"));
+        assert_eq!(segments.strip("page:x:next"), None);
+        assert_eq!(segments.strip("old-page:12:next"), None);
+    }
+}
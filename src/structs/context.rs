@@ -172,10 +172,16 @@ context_methods! {
     /// Create a [`crate::CooldownContext`] based off the underlying context type.
     (cooldown_context self)
     (pub fn cooldown_context(self) -> crate::CooldownContext) {
+        let member_roles = match self {
+            Self::Application(ctx) => ctx.interaction.member.as_ref().map(|m| &m.roles),
+            Self::Prefix(ctx) => ctx.msg.member.as_ref().map(|m| &m.roles),
+        };
         crate::CooldownContext {
             user_id: self.author().id,
             channel_id: self.channel_id(),
-            guild_id: self.guild_id()
+            guild_id: self.guild_id(),
+            roles: member_roles.cloned().unwrap_or_default(),
+            __non_exhaustive: (),
         }
     }
 
@@ -644,6 +650,8 @@ macro_rules! context_trait_impls {
 context_trait_impls!(Context);
 context_trait_impls!(crate::ApplicationContext);
 context_trait_impls!(crate::PrefixContext);
+context_trait_impls!(crate::ComponentContext);
+context_trait_impls!(crate::ModalContext);
 
 /// Trimmed down, more general version of [`Context`]
 pub struct PartialContext<'a, U, E> {
@@ -49,6 +49,15 @@ pub enum FrameworkError<'a, U, E> {
         /// General context
         ctx: crate::Context<'a, U, E>,
     },
+    /// Command took longer than [`crate::Command::timeout`] or
+    /// [`crate::FrameworkOptions::command_timeout`] and was cancelled
+    #[non_exhaustive]
+    CommandTimeout {
+        /// The timeout which was exceeded
+        timeout: std::time::Duration,
+        /// General context
+        ctx: crate::Context<'a, U, E>,
+    },
     /// Panic occured at any phase of command execution after constructing the `crate::Context`.
     ///
     /// This feature is intended as a last-resort safeguard to gracefully print an error message to
@@ -94,6 +103,21 @@ pub enum FrameworkError<'a, U, E> {
     CooldownHit {
         /// Time until the command may be invoked for the next time in the given context
         remaining_cooldown: std::time::Duration,
+        /// Number of invocations that will be available once [`Self::CooldownHit::remaining_cooldown`]
+        /// has passed. Always one for single-use cooldowns
+        remaining_uses: u32,
+        /// The [`crate::Command::cooldown_group`] whose shared cooldown was hit, if any
+        cooldown_group: Option<&'a str>,
+        /// General context
+        ctx: crate::Context<'a, U, E>,
+    },
+    /// Command was invoked while [`crate::Command::max_concurrency`] invocations were already
+    /// running, and [`crate::MaxConcurrency::queue`] wasn't set or the invocation waited longer
+    /// than [`crate::MaxConcurrency::queue_timeout`]
+    #[non_exhaustive]
+    ConcurrencyLimit {
+        /// The limit which was reached
+        max_concurrency: &'a crate::MaxConcurrency,
         /// General context
         ctx: crate::Context<'a, U, E>,
     },
@@ -116,6 +140,21 @@ pub enum FrameworkError<'a, U, E> {
         /// General context
         ctx: crate::Context<'a, U, E>,
     },
+    /// Command was invoked but the user doesn't satisfy [`crate::Command::required_roles`],
+    /// [`crate::Command::any_role`] or [`crate::Command::forbidden_roles`]
+    ///
+    /// If the user's roles couldn't be retrieved, all configured roles are listed.
+    #[non_exhaustive]
+    MissingRoles {
+        /// Roles from [`crate::Command::required_roles`] which the user is lacking
+        missing_roles: Vec<crate::RoleIdentifier>,
+        /// [`crate::Command::any_role`] if the user has none of them, otherwise empty
+        missing_any_role: Vec<crate::RoleIdentifier>,
+        /// Roles from [`crate::Command::forbidden_roles`] which the user has
+        forbidden_roles: Vec<crate::RoleIdentifier>,
+        /// General context
+        ctx: crate::Context<'a, U, E>,
+    },
     /// A non-owner tried to invoke an owners-only command
     #[non_exhaustive]
     NotAnOwner {
@@ -140,12 +179,35 @@ pub enum FrameworkError<'a, U, E> {
         /// General context
         ctx: crate::Context<'a, U, E>,
     },
+    /// Command was invoked in a kind of channel not listed in [`crate::Command::channel_kinds`]
+    #[non_exhaustive]
+    ChannelKindNotAllowed {
+        /// The kinds of channels the command may run in
+        allowed_kinds: Vec<crate::ChannelKind>,
+        /// General context
+        ctx: crate::Context<'a, U, E>,
+    },
+    /// Command was invoked in a channel forbidden by
+    /// [`crate::FrameworkOptions::dynamic_channel_restrictions`]
+    #[non_exhaustive]
+    ChannelNotAllowed {
+        /// General context
+        ctx: crate::Context<'a, U, E>,
+    },
+    /// Command, or one of its parents, was disabled via [`crate::CommandRegistry::disable`]
+    #[non_exhaustive]
+    CommandDisabled {
+        /// General context
+        ctx: crate::Context<'a, U, E>,
+    },
     /// Provided pre-command check either errored, or returned false, so command execution aborted
     #[non_exhaustive]
     CommandCheckFailed {
         /// If execution wasn't aborted because of an error but because it successfully returned
         /// false, this field is None
         error: Option<E>,
+        /// Why access was denied, if the check was a [`crate::Check`] which gave a reason
+        denial: Option<crate::CheckDenial>,
         /// General context
         ctx: crate::Context<'a, U, E>,
     },
@@ -210,6 +272,30 @@ pub enum FrameworkError<'a, U, E> {
         /// The interaction in question
         msg: &'a serenity::Message,
     },
+    /// User code threw an error in a [`crate::ComponentHandler`]
+    #[non_exhaustive]
+    ComponentHandler {
+        /// Error which was thrown in the component handler code
+        error: E,
+        /// Component interaction context
+        ctx: crate::ComponentContext<'a, U, E>,
+    },
+    /// A modal submission failed to parse via [`crate::Modal::parse`]
+    #[non_exhaustive]
+    ModalParse {
+        /// Error which was returned by [`crate::Modal::parse`]
+        error: &'static str,
+        /// Modal submission context
+        ctx: crate::ModalContext<'a, U, E>,
+    },
+    /// User code threw an error in a [`crate::ModalHandler`]
+    #[non_exhaustive]
+    ModalHandler {
+        /// Error which was thrown in the modal handler code
+        error: E,
+        /// Modal submission context
+        ctx: crate::ModalContext<'a, U, E>,
+    },
     // #[non_exhaustive] forbids struct update syntax for ?? reason
     #[doc(hidden)]
     __NonExhaustive(std::convert::Infallible),
@@ -223,21 +309,30 @@ impl<'a, U, E> FrameworkError<'a, U, E> {
             Self::EventHandler { ctx, .. } => ctx,
             Self::Command { ctx, .. } => ctx.serenity_context(),
             Self::SubcommandRequired { ctx } => ctx.serenity_context(),
+            Self::CommandTimeout { ctx, .. } => ctx.serenity_context(),
             Self::CommandPanic { ctx, .. } => ctx.serenity_context(),
             Self::ArgumentParse { ctx, .. } => ctx.serenity_context(),
             Self::CommandStructureMismatch { ctx, .. } => ctx.serenity_context,
             Self::CooldownHit { ctx, .. } => ctx.serenity_context(),
+            Self::ConcurrencyLimit { ctx, .. } => ctx.serenity_context(),
             Self::MissingBotPermissions { ctx, .. } => ctx.serenity_context(),
             Self::MissingUserPermissions { ctx, .. } => ctx.serenity_context(),
+            Self::MissingRoles { ctx, .. } => ctx.serenity_context(),
             Self::NotAnOwner { ctx, .. } => ctx.serenity_context(),
             Self::GuildOnly { ctx, .. } => ctx.serenity_context(),
             Self::DmOnly { ctx, .. } => ctx.serenity_context(),
             Self::NsfwOnly { ctx, .. } => ctx.serenity_context(),
+            Self::ChannelKindNotAllowed { ctx, .. } => ctx.serenity_context(),
+            Self::ChannelNotAllowed { ctx, .. } => ctx.serenity_context(),
+            Self::CommandDisabled { ctx, .. } => ctx.serenity_context(),
             Self::CommandCheckFailed { ctx, .. } => ctx.serenity_context(),
             Self::DynamicPrefix { ctx, .. } => ctx.serenity_context,
             Self::UnknownCommand { ctx, .. } => ctx,
             Self::UnknownInteraction { ctx, .. } => ctx,
             Self::NonCommandMessage { ctx, .. } => ctx,
+            Self::ComponentHandler { ctx, .. } => ctx.serenity_context,
+            Self::ModalParse { ctx, .. } => ctx.serenity_context,
+            Self::ModalHandler { ctx, .. } => ctx.serenity_context,
             Self::__NonExhaustive(unreachable) => match unreachable {},
         }
     }
@@ -247,23 +342,32 @@ impl<'a, U, E> FrameworkError<'a, U, E> {
         Some(match *self {
             Self::Command { ctx, .. } => ctx,
             Self::SubcommandRequired { ctx } => ctx,
+            Self::CommandTimeout { ctx, .. } => ctx,
             Self::CommandPanic { ctx, .. } => ctx,
             Self::ArgumentParse { ctx, .. } => ctx,
             Self::CommandStructureMismatch { ctx, .. } => crate::Context::Application(ctx),
             Self::CooldownHit { ctx, .. } => ctx,
+            Self::ConcurrencyLimit { ctx, .. } => ctx,
             Self::MissingBotPermissions { ctx, .. } => ctx,
             Self::MissingUserPermissions { ctx, .. } => ctx,
+            Self::MissingRoles { ctx, .. } => ctx,
             Self::NotAnOwner { ctx, .. } => ctx,
             Self::GuildOnly { ctx, .. } => ctx,
             Self::DmOnly { ctx, .. } => ctx,
             Self::NsfwOnly { ctx, .. } => ctx,
+            Self::ChannelKindNotAllowed { ctx, .. } => ctx,
+            Self::ChannelNotAllowed { ctx, .. } => ctx,
+            Self::CommandDisabled { ctx, .. } => ctx,
             Self::CommandCheckFailed { ctx, .. } => ctx,
             Self::Setup { .. }
             | Self::EventHandler { .. }
             | Self::UnknownCommand { .. }
             | Self::UnknownInteraction { .. }
             | Self::NonCommandMessage { .. }
-            | Self::DynamicPrefix { .. } => return None,
+            | Self::DynamicPrefix { .. }
+            | Self::ComponentHandler { .. }
+            | Self::ModalParse { .. }
+            | Self::ModalHandler { .. } => return None,
             Self::__NonExhaustive(unreachable) => match unreachable {},
         })
     }
@@ -274,8 +378,96 @@ impl<'a, U, E> FrameworkError<'a, U, E> {
             .ctx()
             .and_then(|c| c.command().on_error)
             .unwrap_or(framework_options.on_error);
+        framework_options.metrics.record_error(self.variant_name());
         on_error(self).await;
     }
+
+    /// Returns the name of this error's variant, e.g. `"CooldownHit"`. Used as a label by
+    /// [`crate::MetricsSink::record_error`]
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Self::Setup { .. } => "Setup",
+            Self::EventHandler { .. } => "EventHandler",
+            Self::Command { .. } => "Command",
+            Self::SubcommandRequired { .. } => "SubcommandRequired",
+            Self::CommandTimeout { .. } => "CommandTimeout",
+            Self::CommandPanic { .. } => "CommandPanic",
+            Self::ArgumentParse { .. } => "ArgumentParse",
+            Self::CommandStructureMismatch { .. } => "CommandStructureMismatch",
+            Self::CooldownHit { .. } => "CooldownHit",
+            Self::ConcurrencyLimit { .. } => "ConcurrencyLimit",
+            Self::MissingBotPermissions { .. } => "MissingBotPermissions",
+            Self::MissingUserPermissions { .. } => "MissingUserPermissions",
+            Self::MissingRoles { .. } => "MissingRoles",
+            Self::NotAnOwner { .. } => "NotAnOwner",
+            Self::GuildOnly { .. } => "GuildOnly",
+            Self::DmOnly { .. } => "DmOnly",
+            Self::NsfwOnly { .. } => "NsfwOnly",
+            Self::ChannelKindNotAllowed { .. } => "ChannelKindNotAllowed",
+            Self::ChannelNotAllowed { .. } => "ChannelNotAllowed",
+            Self::CommandDisabled { .. } => "CommandDisabled",
+            Self::CommandCheckFailed { .. } => "CommandCheckFailed",
+            Self::DynamicPrefix { .. } => "DynamicPrefix",
+            Self::UnknownCommand { .. } => "UnknownCommand",
+            Self::UnknownInteraction { .. } => "UnknownInteraction",
+            Self::NonCommandMessage { .. } => "NonCommandMessage",
+            Self::ComponentHandler { .. } => "ComponentHandler",
+            Self::ModalParse { .. } => "ModalParse",
+            Self::ModalHandler { .. } => "ModalHandler",
+            Self::__NonExhaustive(unreachable) => match *unreachable {},
+        }
+    }
+
+    /// Classifies this error as the outcome of a command invocation, as passed to
+    /// [`crate::FrameworkOptions::on_invocation_finished`]
+    pub fn outcome(&self) -> InvocationOutcome {
+        match self {
+            Self::Command { .. } => InvocationOutcome::CommandError,
+            Self::ArgumentParse { .. } => InvocationOutcome::ArgumentParse,
+            Self::CooldownHit { .. } => InvocationOutcome::Cooldown,
+            Self::ConcurrencyLimit { .. } => InvocationOutcome::ConcurrencyLimit,
+            Self::CommandTimeout { .. } => InvocationOutcome::Timeout,
+            Self::CommandPanic { .. } => InvocationOutcome::Panic,
+            Self::MissingBotPermissions { .. }
+            | Self::MissingUserPermissions { .. }
+            | Self::MissingRoles { .. }
+            | Self::NotAnOwner { .. }
+            | Self::GuildOnly { .. }
+            | Self::DmOnly { .. }
+            | Self::NsfwOnly { .. }
+            | Self::ChannelKindNotAllowed { .. }
+            | Self::ChannelNotAllowed { .. }
+            | Self::CommandDisabled { .. }
+            | Self::CommandCheckFailed { .. } => InvocationOutcome::CheckFailed,
+            _ => InvocationOutcome::Other,
+        }
+    }
+}
+
+/// How a command invocation ended, see [`crate::FrameworkOptions::on_invocation_finished`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum InvocationOutcome {
+    /// The command returned Ok
+    Success,
+    /// The command returned an error, see [`FrameworkError::Command`]
+    CommandError,
+    /// The invoker wasn't allowed to run the command, e.g. because of a failed check, missing
+    /// permissions or roles, or a channel restriction
+    CheckFailed,
+    /// The command was on cooldown, see [`FrameworkError::CooldownHit`]
+    Cooldown,
+    /// Too many invocations were already running, see [`FrameworkError::ConcurrencyLimit`]
+    ConcurrencyLimit,
+    /// The arguments couldn't be parsed, see [`FrameworkError::ArgumentParse`]
+    ArgumentParse,
+    /// The command was cancelled, see [`FrameworkError::CommandTimeout`]
+    Timeout,
+    /// The command panicked, see [`FrameworkError::CommandPanic`]
+    Panic,
+    /// Any other framework error, e.g. [`FrameworkError::SubcommandRequired`]
+    Other,
+    #[doc(hidden)]
+    __NonExhaustive,
 }
 
 /// Support functions for the macro, which can't create these #[non_exhaustive] enum variants
@@ -332,6 +524,12 @@ impl<U, E: std::fmt::Display> std::fmt::Display for FrameworkError<'_, U, E> {
                     full_command_name!(ctx)
                 )
             }
+            Self::CommandTimeout { timeout, ctx } => write!(
+                f,
+                "command `{}` timed out after {:?}",
+                full_command_name!(ctx),
+                timeout
+            ),
             Self::CommandPanic { ctx, payload: _ } => {
                 write!(f, "panic in command `{}`", full_command_name!(ctx))
             }
@@ -351,15 +549,38 @@ impl<U, E: std::fmt::Display> std::fmt::Display for FrameworkError<'_, U, E> {
                 full_command_name!(crate::Context::Application(*ctx)),
                 description
             ),
+            Self::CooldownHit {
+                remaining_cooldown,
+                cooldown_group: Some(cooldown_group),
+                ctx,
+                ..
+            } => write!(
+                f,
+                "cooldown of group `{}` hit in command `{}` ({:?} remaining)",
+                cooldown_group,
+                full_command_name!(ctx),
+                remaining_cooldown
+            ),
             Self::CooldownHit {
                 remaining_cooldown,
                 ctx,
+                ..
             } => write!(
                 f,
                 "cooldown hit in command `{}` ({:?} remaining)",
                 full_command_name!(ctx),
                 remaining_cooldown
             ),
+            Self::ConcurrencyLimit {
+                max_concurrency,
+                ctx,
+            } => write!(
+                f,
+                "concurrency limit of {} ({:?} scope) reached in command `{}`",
+                max_concurrency.limit,
+                max_concurrency.scope,
+                full_command_name!(ctx)
+            ),
             Self::MissingBotPermissions {
                 missing_permissions,
                 ctx,
@@ -378,6 +599,11 @@ impl<U, E: std::fmt::Display> std::fmt::Display for FrameworkError<'_, U, E> {
                 missing_permissions,
                 full_command_name!(ctx),
             ),
+            Self::MissingRoles { ctx, .. } => write!(
+                f,
+                "user doesn't have the right roles to execute command `{}`",
+                full_command_name!(ctx),
+            ),
             Self::NotAnOwner { ctx } => write!(
                 f,
                 "owner-only command `{}` cannot be run by non-owners",
@@ -398,7 +624,31 @@ impl<U, E: std::fmt::Display> std::fmt::Display for FrameworkError<'_, U, E> {
                 "nsfw-only command `{}` cannot run in non-nsfw channels",
                 full_command_name!(ctx)
             ),
-            Self::CommandCheckFailed { error: _, ctx } => write!(
+            Self::ChannelKindNotAllowed { allowed_kinds, ctx } => write!(
+                f,
+                "command `{}` cannot run in this kind of channel (allowed: {:?})",
+                full_command_name!(ctx),
+                allowed_kinds,
+            ),
+            Self::ChannelNotAllowed { ctx } => write!(
+                f,
+                "command `{}` cannot run in this channel",
+                full_command_name!(ctx)
+            ),
+            Self::CommandDisabled { ctx } => {
+                write!(f, "command `{}` is disabled", full_command_name!(ctx))
+            }
+            Self::CommandCheckFailed {
+                denial: Some(denial),
+                ctx,
+                ..
+            } => write!(
+                f,
+                "pre-command check for command `{}` denied access: {}",
+                full_command_name!(ctx),
+                denial.key,
+            ),
+            Self::CommandCheckFailed { ctx, .. } => write!(
                 f,
                 "pre-command check for command `{}` either denied access or errored",
                 full_command_name!(ctx)
@@ -427,6 +677,28 @@ impl<U, E: std::fmt::Display> std::fmt::Display for FrameworkError<'_, U, E> {
                     msg.channel_id, msg.id
                 )
             }
+            Self::ComponentHandler { ctx, .. } => {
+                write!(
+                    f,
+                    "error in component handler for custom ID `{}`",
+                    ctx.custom_id()
+                )
+            }
+            Self::ModalParse { error, ctx } => {
+                write!(
+                    f,
+                    "failed to parse submission of modal `{}`: {}",
+                    ctx.custom_id(),
+                    error
+                )
+            }
+            Self::ModalHandler { ctx, .. } => {
+                write!(
+                    f,
+                    "error in modal handler for custom ID `{}`",
+                    ctx.custom_id()
+                )
+            }
             Self::__NonExhaustive(unreachable) => match *unreachable {},
         }
     }
@@ -441,21 +713,30 @@ impl<'a, U: std::fmt::Debug, E: std::error::Error + 'static> std::error::Error
             Self::EventHandler { error, .. } => Some(error),
             Self::Command { error, .. } => Some(error),
             Self::SubcommandRequired { .. } => None,
+            Self::CommandTimeout { .. } => None,
             Self::CommandPanic { .. } => None,
             Self::ArgumentParse { error, .. } => Some(&**error),
             Self::CommandStructureMismatch { .. } => None,
             Self::CooldownHit { .. } => None,
+            Self::ConcurrencyLimit { .. } => None,
             Self::MissingBotPermissions { .. } => None,
             Self::MissingUserPermissions { .. } => None,
+            Self::MissingRoles { .. } => None,
             Self::NotAnOwner { .. } => None,
             Self::GuildOnly { .. } => None,
             Self::DmOnly { .. } => None,
             Self::NsfwOnly { .. } => None,
+            Self::ChannelKindNotAllowed { .. } => None,
+            Self::ChannelNotAllowed { .. } => None,
+            Self::CommandDisabled { .. } => None,
             Self::CommandCheckFailed { error, .. } => error.as_ref().map(|x| x as _),
             Self::DynamicPrefix { error, .. } => Some(error),
             Self::UnknownCommand { .. } => None,
             Self::UnknownInteraction { .. } => None,
             Self::NonCommandMessage { error, .. } => Some(error),
+            Self::ComponentHandler { error, .. } => Some(error),
+            Self::ModalParse { .. } => None,
+            Self::ModalHandler { error, .. } => Some(error),
             Self::__NonExhaustive(unreachable) => match *unreachable {},
         }
     }
@@ -1,4 +1,4 @@
-//! Just contains `FrameworkOptions`
+//! Just contains `FrameworkOptions` and the types used in its fields
 
 use crate::{serenity_prelude as serenity, BoxFuture};
 
@@ -7,7 +7,12 @@ use crate::{serenity_prelude as serenity, BoxFuture};
 #[derivative(Debug(bound = ""))]
 pub struct FrameworkOptions<U, E> {
     /// List of commands in the framework
+    ///
+    /// To change commands while the bot is running, use [`Self::registry`]. See
+    /// [`Self::active_commands`] for the combined result.
     pub commands: Vec<crate::Command<U, E>>,
+    /// Commands enabled, disabled, added or removed at runtime. See [`crate::CommandRegistry`]
+    pub registry: crate::CommandRegistry<U, E>,
     /// Provide a callback to be invoked when any user code yields an error.
     #[derivative(Debug = "ignore")]
     pub on_error: fn(crate::FrameworkError<'_, U, E>) -> BoxFuture<'_, ()>,
@@ -17,6 +22,27 @@ pub struct FrameworkOptions<U, E> {
     /// Called after every command if it was successful (returned Ok)
     #[derivative(Debug = "ignore")]
     pub post_command: fn(crate::Context<'_, U, E>) -> BoxFuture<'_, ()>,
+    /// Called after every command invocation, regardless of its outcome, with the time it took
+    /// from the checks to the end of the command. Runs before [`Self::on_error`], if there's an
+    /// error.
+    ///
+    /// Invocations which are ignored entirely, e.g. because the invoker is on the
+    /// [`Self::blocklist`], aren't reported. Neither is autocomplete.
+    #[derivative(Debug = "ignore")]
+    pub on_invocation_finished: fn(
+        crate::Context<'_, U, E>,
+        crate::InvocationOutcome,
+        std::time::Duration,
+    ) -> BoxFuture<'_, ()>,
+    /// Middleware which runs around every command action, outermost first. See [`crate::Layer`]
+    pub layers: Vec<crate::Layer<U, E>>,
+    /// If set, commands which take longer than this are cancelled and
+    /// [`crate::FrameworkError::CommandTimeout`] is raised. Can be overridden per command with
+    /// [`crate::Command::timeout`].
+    ///
+    /// Cancellation happens at the command's next `.await`, so commands blocking the thread can't
+    /// be cancelled.
+    pub command_timeout: Option<std::time::Duration>,
     /// Provide a callback to be invoked before every command. The command will only be executed
     /// if the callback returns true.
     ///
@@ -40,6 +66,71 @@ pub struct FrameworkOptions<U, E> {
     /// Useful for implementing custom cooldown behavior. See [`crate::Command::cooldowns`] and
     /// the methods on [`crate::Cooldowns`] for how to do that.
     pub manual_cooldowns: bool,
+    /// Where the automatic cooldown handling stores command invocations.
+    ///
+    /// Defaults to [`crate::InMemoryCooldownStore`]. Replace it with your own
+    /// [`crate::CooldownStore`] implementation to persist cooldowns across restarts.
+    #[derivative(Debug = "ignore")]
+    pub cooldown_store: std::sync::Arc<dyn crate::CooldownStore>,
+    /// Users and guilds which may not use the bot at all. Checked before any other command checks;
+    /// bot owners are never blocked.
+    ///
+    /// Defaults to [`crate::InMemoryBlocklistStore`]. Manage it at runtime with
+    /// [`crate::builtins::blocklist_add`] and related functions.
+    #[derivative(Debug = "ignore")]
+    pub blocklist: std::sync::Arc<dyn crate::BlocklistStore>,
+    /// Response to blocked users when they invoke a command. If None, their invocations are
+    /// ignored silently
+    #[derivative(Debug = "ignore")]
+    pub blocklist_message: Option<fn(&crate::BlocklistEntry) -> String>,
+    /// Receives metrics about command invocations and dispatch, e.g. to show them with
+    /// [`crate::builtins::metrics`] or export them to a monitoring system.
+    ///
+    /// Defaults to [`crate::InMemoryMetricsSink`].
+    #[derivative(Debug = "ignore")]
+    pub metrics: std::sync::Arc<dyn crate::MetricsSink>,
+    /// Callback to determine a command's cooldown configuration at invocation time, e.g. to let
+    /// guild admins set their own cooldowns. Return `None` to use the command's own
+    /// [`crate::Command::cooldown_config`].
+    ///
+    /// Called once per invocation for the command and each of its parents, so consider caching
+    /// expensive lookups. Errors are reported as
+    /// [`crate::FrameworkError::CommandCheckFailed`].
+    ///
+    /// ```rust
+    /// # type Error = Box<dyn std::error::Error + Send + Sync>;
+    /// let options = poise::FrameworkOptions::<(), Error> {
+    ///     dynamic_cooldown_config: Some(|ctx, command| Box::pin(async move {
+    ///         // E.g. look up the guild's settings in your database instead
+    ///         if ctx.guild_id != Some(poise::serenity_prelude::GuildId::new(1234)) {
+    ///             return Ok(None);
+    ///         }
+    ///         let mut config = command.cooldown_config.read().unwrap().clone();
+    ///         config.user = Some(std::time::Duration::from_secs(5));
+    ///         Ok(Some(config))
+    ///     })),
+    ///     ..Default::default()
+    /// };
+    /// ```
+    #[derivative(Debug = "ignore")]
+    pub dynamic_cooldown_config: Option<
+        for<'a> fn(
+            crate::PartialContext<'a, U, E>,
+            &'a crate::Command<U, E>,
+        ) -> BoxFuture<'a, Result<Option<crate::CooldownConfig>, E>>,
+    >,
+    /// Callback to determine in which channels a command may run, e.g. to let guild admins set up
+    /// allow and deny lists. Return `None` to not restrict the command.
+    ///
+    /// Violations are reported as [`crate::FrameworkError::ChannelNotAllowed`], errors as
+    /// [`crate::FrameworkError::CommandCheckFailed`].
+    #[derivative(Debug = "ignore")]
+    pub dynamic_channel_restrictions: Option<
+        for<'a> fn(
+            crate::PartialContext<'a, U, E>,
+            &'a crate::Command<U, E>,
+        ) -> BoxFuture<'a, Result<Option<ChannelRestrictions>, E>>,
+    >,
     /// If `true`, changes behavior of guild_only command check to abort execution if the guild is
     /// not in cache.
     ///
@@ -55,6 +146,16 @@ pub struct FrameworkOptions<U, E> {
         // TODO: redundant with framework
         &'a U,
     ) -> BoxFuture<'a, Result<(), E>>,
+    /// Handlers for message component interactions (buttons, select menus), matched by custom ID.
+    ///
+    /// Component interactions that don't match any handler are left alone, so collectors (like
+    /// the one in [`crate::builtins::paginate`]) keep working.
+    pub component_handlers: Vec<crate::ComponentHandler<U, E>>,
+    /// Handlers for modal submissions, matched by custom ID.
+    ///
+    /// Modal submissions that don't match any handler are left alone, so [`crate::execute_modal`]
+    /// keeps working.
+    pub modal_handlers: Vec<crate::ModalHandler<U, E>>,
     /// Renamed to [`Self::event_handler`]!
     #[deprecated = "renamed to event_handler"]
     pub listener: (),
@@ -73,6 +174,12 @@ pub struct FrameworkOptions<U, E> {
 }
 
 impl<U, E> FrameworkOptions<U, E> {
+    /// Returns the top-level commands currently available: [`Self::commands`] without the ones
+    /// removed via [`Self::registry`], followed by the ones added via [`Self::registry`]
+    pub fn active_commands(&self) -> crate::ActiveCommands<'_, U, E> {
+        self.registry.active_commands(&self.commands)
+    }
+
     /// Add a new command to the framework
     #[deprecated = "supply commands in FrameworkOptions directly with `commands: vec![...]`"]
     pub fn command(
@@ -94,6 +201,7 @@ where
         #[allow(deprecated)] // we need to set the listener field
         Self {
             commands: Vec::new(),
+            registry: crate::CommandRegistry::new(),
             on_error: |error| {
                 Box::pin(async move {
                     if let Err(e) = crate::builtins::on_error(error).await {
@@ -103,8 +211,13 @@ where
             },
             event_handler: |_, _, _, _| Box::pin(async { Ok(()) }),
             listener: (),
+            component_handlers: Vec::new(),
+            modal_handlers: Vec::new(),
             pre_command: |_| Box::pin(async {}),
             post_command: |_| Box::pin(async {}),
+            on_invocation_finished: |_, _, _| Box::pin(async {}),
+            layers: Vec::new(),
+            command_timeout: None,
             command_check: None,
             skip_checks_for_owners: false,
             allowed_mentions: Some(
@@ -116,6 +229,12 @@ where
             ),
             reply_callback: None,
             manual_cooldowns: false,
+            cooldown_store: std::sync::Arc::new(crate::InMemoryCooldownStore::new()),
+            blocklist: std::sync::Arc::new(crate::InMemoryBlocklistStore::new()),
+            blocklist_message: None,
+            metrics: std::sync::Arc::new(crate::InMemoryMetricsSink::new()),
+            dynamic_cooldown_config: None,
+            dynamic_channel_restrictions: None,
             require_cache_for_guild_check: false,
             prefix_options: Default::default(),
             owners: Default::default(),
@@ -124,3 +243,18 @@ where
         }
     }
 }
+
+/// Channels in which a command may or may not run, see
+/// [`FrameworkOptions::dynamic_channel_restrictions`]
+///
+/// Each entry matches the channel itself, channels in the category with that ID, and threads in
+/// either.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct ChannelRestrictions {
+    /// If not empty, the command may only run in these channels
+    pub allowed: Vec<serenity::ChannelId>,
+    /// The command may not run in these channels, even if they're allowed by [`Self::allowed`]
+    pub denied: Vec<serenity::ChannelId>,
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
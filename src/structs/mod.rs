@@ -15,5 +15,8 @@ pub use prefix::*;
 mod slash;
 pub use slash::*;
 
+mod component;
+pub use component::*;
+
 mod framework_error;
 pub use framework_error::*;
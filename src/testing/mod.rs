@@ -0,0 +1,514 @@
+//! Tools for testing your commands end to end, without a connection to Discord.
+//!
+//! [`TestHarness`] runs a tiny local stand-in for the Discord API and points a real
+//! [`serenity::Context`] at it. You inject synthetic events, such as messages or slash command
+//! interactions, and the framework processes them like it would in production: prefix parsing,
+//! argument parsing, checks, cooldowns and [`crate::FrameworkOptions::on_error`] all run as usual.
+//! Every message, edit, defer and deletion the framework sends is recorded as a
+//! [`RecordedAction`] for your test to assert on.
+//!
+//! ```rust
+//! # type Error = Box<dyn std::error::Error + Send + Sync>;
+//! # type Context<'a> = poise::Context<'a, (), Error>;
+//! use poise::serenity_prelude as serenity;
+//!
+//! #[poise::command(prefix_command, slash_command)]
+//! async fn ping(ctx: Context<'_>) -> Result<(), Error> {
+//!     ctx.say("Pong!").await?;
+//!     Ok(())
+//! }
+//!
+//! # #[tokio::main(flavor = "current_thread")] async fn main() -> Result<(), Error> {
+//! let options = poise::FrameworkOptions {
+//!     commands: vec![ping()],
+//!     prefix_options: poise::PrefixFrameworkOptions {
+//!         prefix: Some("~".into()),
+//!         ..Default::default()
+//!     },
+//!     ..Default::default()
+//! };
+//! let harness = poise::testing::TestHarness::new(options, ()).await?;
+//!
+//! harness.dispatch_message(harness.message("~ping")).await;
+//! let actions = harness.take_actions();
+//! assert_eq!(actions.len(), 1);
+//! assert_eq!(actions[0].content(), Some("Pong!"));
+//!
+//! let interaction = harness.command_interaction("ping", serenity::json::json!([]));
+//! harness.dispatch_interaction(serenity::Interaction::Command(interaction)).await;
+//! let actions = harness.take_actions();
+//! assert!(matches!(actions[0], poise::testing::RecordedAction::InteractionResponse { .. }));
+//! assert_eq!(actions[0].content(), Some("Pong!"));
+//! # Ok(()) }
+//! ```
+//!
+//! Not supported are gateway-side features: collectors (and by extension
+//! [`crate::execute_modal`] and [`crate::builtins::paginate`]) never receive events, and the
+//! cache only knows about the bot user unless you fill it yourself.
+
+mod server;
+
+use crate::serenity_prelude as serenity;
+use std::sync::Arc;
+
+/// A state-changing request that the framework sent to the (stand-in) Discord API
+///
+/// Payloads are the raw JSON bodies as sent by serenity, e.g. `{"content": "Pong!", ...}`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordedAction {
+    /// A message was sent to a channel, e.g. a prefix command reply
+    CreateMessage {
+        /// Channel the message was sent to
+        channel_id: serenity::ChannelId,
+        /// Message body
+        payload: serenity::json::Value,
+    },
+    /// A message was edited, e.g. a prefix command reply being edited
+    EditMessage {
+        /// Channel the message is in
+        channel_id: serenity::ChannelId,
+        /// Message that was edited
+        message_id: serenity::MessageId,
+        /// New message body
+        payload: serenity::json::Value,
+    },
+    /// A message was deleted
+    DeleteMessage {
+        /// Channel the message was in
+        channel_id: serenity::ChannelId,
+        /// Message that was deleted
+        message_id: serenity::MessageId,
+    },
+    /// An initial interaction response was sent, e.g. a slash command reply, a defer or an
+    /// autocomplete response
+    InteractionResponse {
+        /// Interaction that was responded to
+        interaction_id: serenity::InteractionId,
+        /// [Interaction response type](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-response-object-interaction-callback-type),
+        /// e.g. 4 for a message or 5 for a defer
+        kind: u64,
+        /// The `data` field of the interaction response
+        payload: serenity::json::Value,
+    },
+    /// The initial interaction response was edited
+    EditResponse {
+        /// New message body
+        payload: serenity::json::Value,
+    },
+    /// The initial interaction response was deleted
+    DeleteResponse,
+    /// A followup message was sent to an interaction
+    CreateFollowup {
+        /// Message body
+        payload: serenity::json::Value,
+    },
+    /// A followup message was edited
+    EditFollowup {
+        /// Followup message that was edited
+        message_id: serenity::MessageId,
+        /// New message body
+        payload: serenity::json::Value,
+    },
+    /// A followup message was deleted
+    DeleteFollowup {
+        /// Followup message that was deleted
+        message_id: serenity::MessageId,
+    },
+    /// Any other non-GET request. Answered with 404 unless set up with
+    /// [`TestHarness::set_response`]
+    Other {
+        /// HTTP method, like `PUT`
+        method: String,
+        /// Request path relative to the API base, like `/channels/123/pins/456`
+        path: String,
+        /// Request body, if any
+        payload: Option<serenity::json::Value>,
+    },
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+impl RecordedAction {
+    /// Returns the request payload, if this action has one
+    pub fn payload(&self) -> Option<&serenity::json::Value> {
+        match self {
+            Self::CreateMessage { payload, .. }
+            | Self::EditMessage { payload, .. }
+            | Self::InteractionResponse { payload, .. }
+            | Self::EditResponse { payload }
+            | Self::CreateFollowup { payload }
+            | Self::EditFollowup { payload, .. } => Some(payload),
+            Self::Other { payload, .. } => payload.as_ref(),
+            Self::DeleteMessage { .. }
+            | Self::DeleteResponse
+            | Self::DeleteFollowup { .. }
+            | Self::__NonExhaustive => None,
+        }
+    }
+
+    /// Returns the message content of the payload, if any
+    pub fn content(&self) -> Option<&str> {
+        self.payload()?.get("content")?.as_str()
+    }
+
+    /// Whether the payload has the ephemeral message flag set
+    pub fn is_ephemeral(&self) -> bool {
+        let flags = self.payload().and_then(|p| p.get("flags")?.as_u64());
+        flags.unwrap_or(0) & serenity::MessageFlags::EPHEMERAL.bits() != 0
+    }
+
+    /// Whether this is a deferred interaction response (type 5 or 6)
+    pub fn is_defer(&self) -> bool {
+        matches!(self, Self::InteractionResponse { kind: 5 | 6, .. })
+    }
+}
+
+/// Drives the framework with synthetic events against a local stand-in for the Discord API.
+///
+/// See the [module docs](self) for an example.
+pub struct TestHarness<U, E> {
+    /// Framework options, with qualified names already set
+    options: crate::FrameworkOptions<U, E>,
+    /// User data passed to commands
+    user_data: U,
+    /// User ID of the fake bot
+    bot_id: serenity::UserId,
+    /// Serenity context with HTTP pointed to the stand-in server
+    serenity_context: serenity::Context,
+    /// Shard manager, needed to construct [`crate::FrameworkContext`]
+    shard_manager: Arc<serenity::ShardManager>,
+    /// Stand-in Discord API that records the framework's requests
+    server: server::MockDiscord,
+    /// Used to generate IDs for synthetic events
+    next_id: std::sync::atomic::AtomicU64,
+
+    /// Author of messages and interactions created by [`Self::message`] and
+    /// [`Self::command_interaction`]
+    pub author: serenity::User,
+    /// Channel of messages and interactions created by [`Self::message`] and
+    /// [`Self::command_interaction`]
+    pub channel_id: serenity::ChannelId,
+    /// Guild of messages and interactions created by [`Self::message`] and
+    /// [`Self::command_interaction`]. None means DMs
+    pub guild_id: Option<serenity::GuildId>,
+}
+
+impl<U: Send + Sync, E> TestHarness<U, E> {
+    /// Starts the stand-in Discord API and sets up the framework with the given options and user
+    /// data.
+    ///
+    /// Unlike [`crate::Framework`], owners aren't fetched from the API, so set
+    /// [`crate::FrameworkOptions::owners`] yourself if you need them.
+    pub async fn new(
+        mut options: crate::FrameworkOptions<U, E>,
+        user_data: U,
+    ) -> Result<Self, serenity::Error> {
+        crate::set_qualified_names(&mut options.commands);
+        for error in crate::validate_commands(&options.commands) {
+            tracing::warn!("Invalid command: {error}");
+        }
+
+        let bot_id = serenity::UserId::new(1000);
+        let mut bot_user = serenity::User::default();
+        bot_user.id = bot_id;
+        bot_user.name = "TestBot".into();
+        bot_user.bot = true;
+
+        let mut author = serenity::User::default();
+        author.id = serenity::UserId::new(2000);
+        author.name = "tester".into();
+
+        let server = server::MockDiscord::start(bot_user.clone()).await?;
+        let token = "test-token";
+
+        let http = Arc::new(
+            serenity::HttpBuilder::new(token)
+                .proxy(server.http_url.clone())
+                .ratelimiter_disabled(true)
+                .application_id(serenity::ApplicationId::new(bot_id.get()))
+                .build(),
+        );
+        #[cfg(feature = "cache")]
+        let cache = Arc::new(serenity::Cache::new());
+        // serenity checks message authorship against the cached current user before editing
+        #[cfg(feature = "cache")]
+        {
+            #[allow(unused_imports)] // required for simd-json
+            use ::serenity::json::*;
+
+            let ready = json!({
+                "v": 10,
+                "user": to_value(&bot_user).unwrap_or(NULL),
+                "guilds": [],
+                "session_id": "test-session",
+                "resume_gateway_url": server.gateway_url.clone(),
+                "application": { "id": bot_id.to_string(), "flags": 0 },
+            });
+            let mut ready = from_value::<serenity::ReadyEvent>(ready)?;
+            cache.update(&mut ready);
+        }
+        let data = Arc::new(::serenity::prelude::RwLock::new(
+            ::serenity::prelude::TypeMap::new(),
+        ));
+        let ws_url = Arc::new(::serenity::prelude::Mutex::new(server.gateway_url.clone()));
+
+        let (shard_manager, _) = serenity::ShardManager::new(serenity::ShardManagerOptions {
+            data: data.clone(),
+            event_handlers: vec![],
+            raw_event_handlers: vec![],
+            framework: Arc::new(std::sync::OnceLock::new()),
+            shard_index: 0,
+            shard_init: 1,
+            shard_total: 1,
+            ws_url: ws_url.clone(),
+            #[cfg(feature = "cache")]
+            cache: cache.clone(),
+            http: http.clone(),
+            intents: serenity::GatewayIntents::all(),
+            presence: None,
+        });
+        let shard = serenity::Shard::new(
+            ws_url,
+            token,
+            serenity::ShardInfo {
+                id: serenity::ShardId(0),
+                total: 1,
+            },
+            serenity::GatewayIntents::all(),
+            None,
+        )
+        .await?;
+        // The runner is never started, it only exists to create a messenger
+        let runner = serenity::ShardRunner::new(serenity::ShardRunnerOptions {
+            data: data.clone(),
+            event_handlers: vec![],
+            raw_event_handlers: vec![],
+            framework: None,
+            manager: shard_manager.clone(),
+            shard,
+            #[cfg(feature = "cache")]
+            cache: cache.clone(),
+            http: http.clone(),
+        });
+
+        let serenity_context = serenity::Context {
+            data,
+            shard: serenity::ShardMessenger::new(&runner),
+            shard_id: serenity::ShardId(0),
+            http,
+            #[cfg(feature = "cache")]
+            cache,
+        };
+
+        Ok(Self {
+            options,
+            user_data,
+            bot_id,
+            serenity_context,
+            shard_manager,
+            server,
+            next_id: std::sync::atomic::AtomicU64::new(1 << 40),
+            author,
+            channel_id: serenity::ChannelId::new(3000),
+            guild_id: Some(serenity::GuildId::new(4000)),
+        })
+    }
+
+    /// Returns a [`crate::FrameworkContext`] for use with the functions in [`crate::dispatch`]
+    pub fn framework(&self) -> crate::FrameworkContext<'_, U, E> {
+        crate::FrameworkContext {
+            bot_id: self.bot_id,
+            options: &self.options,
+            user_data: &self.user_data,
+            shard_manager: &self.shard_manager,
+        }
+    }
+
+    /// Returns the serenity context whose HTTP client talks to the stand-in Discord API
+    pub fn serenity_context(&self) -> &serenity::Context {
+        &self.serenity_context
+    }
+
+    /// Runs the given event through [`crate::dispatch_event`] and waits for it to be processed
+    pub async fn dispatch(&self, event: serenity::FullEvent) {
+        crate::dispatch_event(self.framework(), &self.serenity_context, event).await;
+    }
+
+    /// Shorthand for dispatching a [`serenity::FullEvent::Message`]
+    pub async fn dispatch_message(&self, new_message: serenity::Message) {
+        self.dispatch(serenity::FullEvent::Message { new_message })
+            .await;
+    }
+
+    /// Shorthand for dispatching a [`serenity::FullEvent::InteractionCreate`]
+    pub async fn dispatch_interaction(&self, interaction: serenity::Interaction) {
+        self.dispatch(serenity::FullEvent::InteractionCreate { interaction })
+            .await;
+    }
+
+    /// Generates a new unique snowflake for synthetic events
+    fn next_id(&self) -> u64 {
+        self.next_id
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Creates a message with the given content, sent by [`Self::author`] in [`Self::channel_id`]
+    /// and [`Self::guild_id`]
+    pub fn message(&self, content: impl Into<String>) -> serenity::Message {
+        let mut message = serenity::Message::default();
+        message.id = serenity::MessageId::new(self.next_id());
+        message.channel_id = self.channel_id;
+        message.guild_id = self.guild_id;
+        message.author = self.author.clone();
+        message.content = content.into();
+        message.timestamp = serenity::Timestamp::now();
+        message
+    }
+
+    /// Creates a slash command interaction for the given top-level command name, invoked by
+    /// [`Self::author`] in [`Self::channel_id`] and [`Self::guild_id`]
+    ///
+    /// `options` is the raw JSON array of
+    /// [interaction data options](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-object-application-command-interaction-data-option-structure),
+    /// e.g. `json!([{"name": "amount", "type": 4, "value": 5}])`. Subcommands are options, too.
+    ///
+    /// # Panics
+    /// Panics if `options` is malformed
+    pub fn command_interaction(
+        &self,
+        name: &str,
+        options: serenity::json::Value,
+    ) -> serenity::CommandInteraction {
+        #[allow(unused_imports)] // required for simd-json
+        use ::serenity::json::*;
+
+        let data = json!({
+            "id": self.next_id().to_string(),
+            "name": name,
+            "type": 1,
+            "options": options,
+        });
+        serenity::json::from_value(self.interaction_json(2, data, None))
+            .expect("invalid command interaction options")
+    }
+
+    /// Creates a button interaction with the given custom ID, invoked by [`Self::author`] in
+    /// [`Self::channel_id`] and [`Self::guild_id`]
+    ///
+    /// The message the button is attached to is an empty message by the bot; pass your own
+    /// with `message` if your handler looks at it.
+    pub fn component_interaction(
+        &self,
+        custom_id: &str,
+        message: Option<serenity::Message>,
+    ) -> serenity::ComponentInteraction {
+        #[allow(unused_imports)] // required for simd-json
+        use ::serenity::json::*;
+
+        let message = message.unwrap_or_else(|| {
+            let mut message = self.message("");
+            message.author = serenity::User::default();
+            message.author.id = self.bot_id;
+            message.author.bot = true;
+            message
+        });
+        let data = json!({
+            "custom_id": custom_id,
+            "component_type": 2,
+        });
+        serenity::json::from_value(self.interaction_json(3, data, Some(message)))
+            .expect("invalid component interaction")
+    }
+
+    /// Creates a modal submission with the given custom ID and text input values, submitted by
+    /// [`Self::author`] in [`Self::channel_id`] and [`Self::guild_id`]
+    ///
+    /// `fields` are pairs of text input custom ID and value. For modals derived with
+    /// `#[derive(poise::Modal)]`, the text input custom IDs are the struct field names.
+    pub fn modal_interaction(
+        &self,
+        custom_id: &str,
+        fields: &[(&str, &str)],
+    ) -> serenity::ModalInteraction {
+        #[allow(unused_imports)] // required for simd-json
+        use ::serenity::json::*;
+
+        let rows = fields
+            .iter()
+            .map(|(field_id, value)| {
+                json!({
+                    "type": 1,
+                    "components": [{ "type": 4, "custom_id": field_id, "value": value }],
+                })
+            })
+            .collect::<Vec<_>>();
+        let data = json!({
+            "custom_id": custom_id,
+            "components": rows,
+        });
+        serenity::json::from_value(self.interaction_json(5, data, None))
+            .expect("invalid modal interaction")
+    }
+
+    /// Builds the JSON of an interaction by [`Self::author`] with the given type and data
+    fn interaction_json(
+        &self,
+        kind: u8,
+        data: serenity::json::Value,
+        message: Option<serenity::Message>,
+    ) -> serenity::json::Value {
+        #[allow(unused_imports)] // required for simd-json
+        use ::serenity::json::*;
+
+        let user = to_value(&self.author).unwrap_or(NULL);
+        let mut interaction = json!({
+            "id": self.next_id().to_string(),
+            "application_id": self.bot_id.to_string(),
+            "type": kind,
+            "data": data,
+            "channel_id": self.channel_id.to_string(),
+            "token": "interaction-token",
+            "version": 1,
+            "locale": "en-US",
+            "entitlements": [],
+        });
+        if let Some(message) = message {
+            interaction["message"] = to_value(message).unwrap_or(NULL);
+        }
+        match self.guild_id {
+            Some(guild_id) => {
+                interaction["guild_id"] = json!(guild_id.to_string());
+                interaction["member"] = json!({
+                    "user": user,
+                    "roles": [],
+                    "joined_at": "2015-05-13T00:00:00Z",
+                    "deaf": false,
+                    "mute": false,
+                    "flags": 0,
+                    "permissions": serenity::Permissions::all().bits().to_string(),
+                });
+            }
+            None => interaction["user"] = user,
+        }
+        interaction
+    }
+
+    /// Returns all actions recorded so far
+    pub fn actions(&self) -> Vec<RecordedAction> {
+        self.server.actions()
+    }
+
+    /// Returns all actions recorded so far and clears the record
+    pub fn take_actions(&self) -> Vec<RecordedAction> {
+        self.server.take_actions()
+    }
+
+    /// Sets the JSON returned for requests to the given method and path, e.g.
+    /// `("GET", "/channels/3000")`. Use this for API calls the stand-in doesn't understand by
+    /// default, which are answered with 404. A `null` body is sent as an empty 204 response.
+    ///
+    /// Non-GET requests answered this way are recorded as [`RecordedAction::Other`].
+    pub fn set_response(&self, method: &str, path: &str, body: serenity::json::Value) {
+        self.server.set_response(method, path, body);
+    }
+}
@@ -0,0 +1,409 @@
+//! A tiny local stand-in for the Discord HTTP API and gateway, just capable enough to let serenity
+//! believe it's talking to Discord
+
+use super::RecordedAction;
+use crate::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt as _, AsyncReadExt as _, AsyncWriteExt as _};
+
+/// A single parsed HTTP request
+struct Request {
+    /// HTTP method, like `POST`
+    method: String,
+    /// Request path without the `/api/v10` prefix and without query string
+    path: String,
+    /// JSON payload of the request, if any. For multipart requests, this is the `payload_json` part
+    payload: Option<serenity::json::Value>,
+}
+
+/// State shared between the [`MockDiscord`] handle and the connection tasks
+struct State {
+    /// Every state-changing request that was received, in order
+    actions: Mutex<Vec<RecordedAction>>,
+    /// Canned responses set by the user, keyed by method and path
+    responses: Mutex<HashMap<(String, String), serenity::json::Value>>,
+    /// Used to generate IDs for messages created by the bot
+    next_id: std::sync::atomic::AtomicU64,
+    /// User object of the bot, used as author of created messages
+    bot_user: serenity::User,
+}
+
+/// Handle to the running stand-in servers. Aborts the server tasks on drop
+pub(super) struct MockDiscord {
+    /// Base URL of the HTTP API, to be passed to [`serenity::HttpBuilder::proxy`]
+    pub http_url: String,
+    /// URL of the websocket gateway
+    pub gateway_url: String,
+    /// See [`State`]
+    state: Arc<State>,
+    /// Handles to the listener tasks in order to `abort()` them on `Drop`
+    tasks: Vec<tokio::task::JoinHandle<()>>,
+}
+
+impl MockDiscord {
+    /// Binds the HTTP and gateway listeners to random local ports and starts serving
+    pub async fn start(bot_user: serenity::User) -> std::io::Result<Self> {
+        let state = Arc::new(State {
+            actions: Mutex::new(Vec::new()),
+            responses: Mutex::new(HashMap::new()),
+            next_id: std::sync::atomic::AtomicU64::new(1 << 32),
+            bot_user,
+        });
+
+        let http_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let gateway_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let http_url = format!("http://{}", http_listener.local_addr()?);
+        let gateway_url = format!("ws://{}", gateway_listener.local_addr()?);
+
+        let http_state = state.clone();
+        let http_task = tokio::spawn(async move {
+            while let Ok((stream, _)) = http_listener.accept().await {
+                let state = http_state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_http_connection(stream, &state).await {
+                        tracing::warn!("mock Discord HTTP connection failed: {}", e);
+                    }
+                });
+            }
+        });
+        // The gateway connection is only needed to construct a shard. We complete the websocket
+        // handshake and then keep the connection open without ever sending anything
+        let gateway_task = tokio::spawn(async move {
+            while let Ok((stream, _)) = gateway_listener.accept().await {
+                tokio::spawn(async move {
+                    use futures_util::StreamExt as _;
+
+                    if let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await {
+                        while let Some(Ok(_)) = ws.next().await {}
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            http_url,
+            gateway_url,
+            state,
+            tasks: vec![http_task, gateway_task],
+        })
+    }
+
+    /// Returns all recorded actions without clearing them
+    pub fn actions(&self) -> Vec<RecordedAction> {
+        self.state.actions.lock().unwrap().clone()
+    }
+
+    /// Returns all recorded actions and clears the record
+    pub fn take_actions(&self) -> Vec<RecordedAction> {
+        std::mem::take(&mut *self.state.actions.lock().unwrap())
+    }
+
+    /// Sets the JSON body returned for requests with the given method and path
+    pub fn set_response(&self, method: &str, path: &str, body: serenity::json::Value) {
+        self.state
+            .responses
+            .lock()
+            .unwrap()
+            .insert((method.to_uppercase(), path.to_owned()), body);
+    }
+}
+
+impl Drop for MockDiscord {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Reads requests from the connection and answers them until the client hangs up
+async fn serve_http_connection(
+    stream: tokio::net::TcpStream,
+    state: &State,
+) -> std::io::Result<()> {
+    let mut stream = tokio::io::BufReader::new(stream);
+    while let Some(request) = read_request(&mut stream).await? {
+        let (status, body) = handle_request(state, request);
+
+        let status_text = match status {
+            200 => "OK",
+            204 => "No Content",
+            _ => "Not Found",
+        };
+        let body = match body {
+            Some(body) => serenity::json::to_vec(&body).map_err(std::io::Error::other)?,
+            None => Vec::new(),
+        };
+        let head = format!(
+            "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+            status,
+            status_text,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&body).await?;
+        stream.flush().await?;
+    }
+    Ok(())
+}
+
+/// Parses a single HTTP/1.1 request off the stream. Returns None if the connection was closed
+async fn read_request(
+    stream: &mut tokio::io::BufReader<tokio::net::TcpStream>,
+) -> std::io::Result<Option<Request>> {
+    let mut request_line = String::new();
+    if stream.read_line(&mut request_line).await? == 0 {
+        return Ok(None);
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let target = parts.next().unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default();
+    let path = path.strip_prefix("/api/v10").unwrap_or(path).to_owned();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        stream.read_line(&mut line).await?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_ascii_lowercase(), value.trim().to_owned());
+        }
+    }
+
+    let mut body = Vec::new();
+    if let Some(length) = headers.get("content-length") {
+        body.resize(length.parse().unwrap_or(0), 0);
+        stream.read_exact(&mut body).await?;
+    } else if headers.get("transfer-encoding").map(String::as_str) == Some("chunked") {
+        loop {
+            let mut size_line = String::new();
+            stream.read_line(&mut size_line).await?;
+            let size = usize::from_str_radix(size_line.trim(), 16).unwrap_or(0);
+            let mut chunk = vec![0; size + 2]; // chunk data is followed by CRLF
+            stream.read_exact(&mut chunk).await?;
+            if size == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    }
+
+    let content_type = headers.get("content-type").map_or("", String::as_str);
+    let payload = if let Some(boundary) = content_type.split("boundary=").nth(1) {
+        extract_multipart_payload(&body, boundary)
+    } else {
+        serenity::json::from_slice(&body).ok()
+    };
+
+    Ok(Some(Request {
+        method,
+        path,
+        payload,
+    }))
+}
+
+/// Finds the `payload_json` part in a multipart body (used by serenity when sending attachments)
+fn extract_multipart_payload(body: &[u8], boundary: &str) -> Option<serenity::json::Value> {
+    let body = String::from_utf8_lossy(body);
+    body.split(&format!("--{}", boundary))
+        .filter(|part| part.contains("name=\"payload_json\""))
+        .find_map(|part| {
+            let (_headers, content) = part.split_once("\r\n\r\n")?;
+            serenity::json::from_str(content.trim_end().to_owned()).ok()
+        })
+}
+
+/// Records the request if it's state-changing and generates a response
+fn handle_request(state: &State, request: Request) -> (u16, Option<serenity::json::Value>) {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    let Request {
+        method,
+        path,
+        payload,
+    } = request;
+
+    let canned = state
+        .responses
+        .lock()
+        .unwrap()
+        .get(&(method.clone(), path.clone()))
+        .cloned();
+    if let Some(body) = canned {
+        if method != "GET" {
+            state.actions.lock().unwrap().push(RecordedAction::Other {
+                method,
+                path,
+                payload,
+            });
+        }
+        return match body.is_null() {
+            true => (204, None),
+            false => (200, Some(body)),
+        };
+    }
+
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let id = |s: &str| s.parse::<u64>().ok().filter(|&id| id != 0);
+    let payload_or_null = || payload.clone().unwrap_or(NULL);
+
+    let (action, response) = match (method.as_str(), &*segments) {
+        ("POST", ["channels", channel_id, "messages"]) => {
+            let channel_id = serenity::ChannelId::new(id(channel_id).unwrap_or(1));
+            (
+                Some(RecordedAction::CreateMessage {
+                    channel_id,
+                    payload: payload_or_null(),
+                }),
+                Some(message_response(state, channel_id, None, &payload)),
+            )
+        }
+        ("PATCH", ["channels", channel_id, "messages", message_id]) => {
+            let channel_id = serenity::ChannelId::new(id(channel_id).unwrap_or(1));
+            let message_id = serenity::MessageId::new(id(message_id).unwrap_or(1));
+            (
+                Some(RecordedAction::EditMessage {
+                    channel_id,
+                    message_id,
+                    payload: payload_or_null(),
+                }),
+                Some(message_response(
+                    state,
+                    channel_id,
+                    Some(message_id),
+                    &payload,
+                )),
+            )
+        }
+        ("DELETE", ["channels", channel_id, "messages", message_id]) => (
+            Some(RecordedAction::DeleteMessage {
+                channel_id: serenity::ChannelId::new(id(channel_id).unwrap_or(1)),
+                message_id: serenity::MessageId::new(id(message_id).unwrap_or(1)),
+            }),
+            None,
+        ),
+        ("POST", ["channels", _, "typing"]) => (None, None),
+        ("POST", ["interactions", interaction_id, _token, "callback"]) => {
+            let payload = payload_or_null();
+            (
+                Some(RecordedAction::InteractionResponse {
+                    interaction_id: serenity::InteractionId::new(id(interaction_id).unwrap_or(1)),
+                    kind: payload.get("type").and_then(|x| x.as_u64()).unwrap_or(0),
+                    payload: payload.get("data").cloned().unwrap_or(NULL),
+                }),
+                None,
+            )
+        }
+        ("GET", ["webhooks", _, _, "messages", "@original"]) => (
+            None,
+            Some(message_response(
+                state,
+                serenity::ChannelId::new(1),
+                None,
+                &None,
+            )),
+        ),
+        ("PATCH", ["webhooks", _, _, "messages", "@original"]) => (
+            Some(RecordedAction::EditResponse {
+                payload: payload_or_null(),
+            }),
+            Some(message_response(
+                state,
+                serenity::ChannelId::new(1),
+                None,
+                &payload,
+            )),
+        ),
+        ("DELETE", ["webhooks", _, _, "messages", "@original"]) => {
+            (Some(RecordedAction::DeleteResponse), None)
+        }
+        ("POST", ["webhooks", _, _]) => (
+            Some(RecordedAction::CreateFollowup {
+                payload: payload_or_null(),
+            }),
+            Some(message_response(
+                state,
+                serenity::ChannelId::new(1),
+                None,
+                &payload,
+            )),
+        ),
+        ("PATCH", ["webhooks", _, _, "messages", message_id]) => {
+            let message_id = serenity::MessageId::new(id(message_id).unwrap_or(1));
+            (
+                Some(RecordedAction::EditFollowup {
+                    message_id,
+                    payload: payload_or_null(),
+                }),
+                Some(message_response(
+                    state,
+                    serenity::ChannelId::new(1),
+                    Some(message_id),
+                    &payload,
+                )),
+            )
+        }
+        ("DELETE", ["webhooks", _, _, "messages", message_id]) => (
+            Some(RecordedAction::DeleteFollowup {
+                message_id: serenity::MessageId::new(id(message_id).unwrap_or(1)),
+            }),
+            None,
+        ),
+        _ => {
+            if method != "GET" {
+                state.actions.lock().unwrap().push(RecordedAction::Other {
+                    method,
+                    path,
+                    payload,
+                });
+            }
+            let error = json!({ "code": 0, "message": "Unknown route (poise mock Discord)" });
+            return (404, Some(error));
+        }
+    };
+
+    if let Some(action) = action {
+        state.actions.lock().unwrap().push(action);
+    }
+    match response {
+        Some(response) => (200, Some(response)),
+        None => (204, None),
+    }
+}
+
+/// Builds the message object that Discord would return after creating or editing a message
+fn message_response(
+    state: &State,
+    channel_id: serenity::ChannelId,
+    message_id: Option<serenity::MessageId>,
+    payload: &Option<serenity::json::Value>,
+) -> serenity::json::Value {
+    #[allow(unused_imports)] // required for simd-json
+    use ::serenity::json::*;
+
+    let mut message = serenity::Message::default();
+    message.id = message_id.unwrap_or_else(|| {
+        let id = state
+            .next_id
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        serenity::MessageId::new(id)
+    });
+    message.channel_id = channel_id;
+    message.author = state.bot_user.clone();
+    message.timestamp = serenity::Timestamp::now();
+    if let Some(content) = payload
+        .as_ref()
+        .and_then(|p| p.get("content"))
+        .and_then(|c| c.as_str())
+    {
+        message.content = content.to_owned();
+    }
+
+    serenity::json::to_value(message).unwrap_or(NULL)
+}
@@ -156,6 +156,16 @@ impl EditTracker {
         });
     }
 
+    /// Returns the number of tracked invocation messages
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Returns true if no invocation messages are tracked
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
     /// Given a message by a user, find the corresponding bot response, if one exists and is cached.
     pub fn find_bot_response(
         &self,
@@ -0,0 +1,588 @@
+//! End-to-end tests which dispatch events through [`poise::testing::TestHarness`]
+
+#![cfg(feature = "testing")]
+
+use poise::serenity_prelude as serenity;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// Records what happened during a test, in order
+#[derive(Default)]
+struct Data {
+    events: Mutex<Vec<String>>,
+}
+
+impl Data {
+    fn push(&self, event: impl Into<String>) {
+        self.events.lock().unwrap().push(event.into());
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.events.lock().unwrap())
+    }
+}
+
+#[poise::command(prefix_command, slash_command)]
+async fn ping(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.data().push("ping");
+    ctx.say("Pong!").await?;
+    Ok(())
+}
+
+#[poise::command(prefix_command)]
+async fn help(ctx: Context<'_>, command: Option<String>) -> Result<(), Error> {
+    poise::builtins::help(ctx, command.as_deref(), Default::default()).await?;
+    Ok(())
+}
+
+#[poise::command(prefix_command)]
+async fn fail(_ctx: Context<'_>) -> Result<(), Error> {
+    Err("failed".into())
+}
+
+#[poise::command(prefix_command)]
+async fn sleep(ctx: Context<'_>) -> Result<(), Error> {
+    sleep_inner(ctx).await
+}
+
+#[poise::command(prefix_command, rename = "sleep", max_concurrency = 1)]
+async fn sleep_limited(ctx: Context<'_>) -> Result<(), Error> {
+    sleep_inner(ctx).await
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "sleep",
+    max_concurrency = 1,
+    concurrency_queue
+)]
+async fn sleep_queued(ctx: Context<'_>) -> Result<(), Error> {
+    sleep_inner(ctx).await
+}
+
+async fn sleep_inner(ctx: Context<'_>) -> Result<(), Error> {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    ctx.say("Done").await?;
+    Ok(())
+}
+
+fn skip() -> poise::Layer<Data, Error> {
+    poise::Layer::new(|ctx: Context<'_>, _next| {
+        Box::pin(async move {
+            ctx.data().push("skipped");
+            Ok(())
+        })
+    })
+}
+
+#[poise::command(prefix_command, layer = "skip")]
+async fn skipped(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.data().push("skipped command ran");
+    Ok(())
+}
+
+/// Options with the `~` prefix and an `on_error` which does nothing, so that errors are only
+/// visible through the metrics
+fn options(commands: Vec<poise::Command<Data, Error>>) -> poise::FrameworkOptions<Data, Error> {
+    poise::FrameworkOptions {
+        commands,
+        prefix_options: poise::PrefixFrameworkOptions {
+            prefix: Some("~".into()),
+            ..Default::default()
+        },
+        on_error: |_| Box::pin(async {}),
+        ..Default::default()
+    }
+}
+
+async fn harness(
+    options: poise::FrameworkOptions<Data, Error>,
+) -> poise::testing::TestHarness<Data, Error> {
+    poise::testing::TestHarness::new(options, Data::default())
+        .await
+        .unwrap()
+}
+
+/// Message contents of the recorded actions, clearing the record
+fn take_contents<U: Send + Sync, E>(harness: &poise::testing::TestHarness<U, E>) -> Vec<String> {
+    let actions = harness.take_actions();
+    actions
+        .iter()
+        .filter_map(|action| action.content().map(String::from))
+        .collect()
+}
+
+/// How often each [`poise::FrameworkError`] variant was handled
+fn error_count<U: Send + Sync, E>(
+    harness: &poise::testing::TestHarness<U, E>,
+    variant: &str,
+) -> u64 {
+    let metrics = harness.framework().options().metrics.snapshot().unwrap();
+    metrics.errors.get(variant).copied().unwrap_or(0)
+}
+
+async fn vote(ctx: poise::ComponentContext<'_, Data, Error>) -> Result<(), Error> {
+    ctx.say(format!("You voted {}", ctx.args)).await?;
+    Ok(())
+}
+
+#[derive(poise::Modal)]
+struct Feedback {
+    text: String,
+}
+
+async fn feedback(ctx: poise::ModalContext<'_, Data, Error>, modal: Feedback) -> Result<(), Error> {
+    ctx.say(format!("Thanks for {}", modal.text)).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_component_and_modal_handlers() {
+    let h = harness(poise::FrameworkOptions {
+        component_handlers: vec![poise::ComponentHandler::new(
+            poise::CustomIdPattern::Prefix("vote:"),
+            |ctx| Box::pin(vote(ctx)),
+        )],
+        modal_handlers: vec![poise::ModalHandler::new(
+            poise::CustomIdPattern::Prefix("feedback"),
+            |ctx, modal| Box::pin(feedback(ctx, modal)),
+        )],
+        ..options(vec![])
+    })
+    .await;
+
+    let interaction = h.component_interaction("vote:yes", None);
+    h.dispatch_interaction(serenity::Interaction::Component(interaction))
+        .await;
+    assert_eq!(take_contents(&h), ["You voted yes"]);
+
+    let interaction = h.modal_interaction("feedback", &[("text", "the bot")]);
+    h.dispatch_interaction(serenity::Interaction::Modal(interaction))
+        .await;
+    assert_eq!(take_contents(&h), ["Thanks for the bot"]);
+
+    // Submissions which don't fit the modal are reported instead of reaching the handler
+    let interaction = h.modal_interaction("feedback", &[("other", "the bot")]);
+    h.dispatch_interaction(serenity::Interaction::Modal(interaction))
+        .await;
+    assert!(take_contents(&h).is_empty());
+    assert_eq!(error_count(&h, "ModalParse"), 1);
+
+    // Unmatched interactions are left to collectors
+    let interaction = h.component_interaction("other", None);
+    h.dispatch_interaction(serenity::Interaction::Component(interaction))
+        .await;
+    assert!(h.take_actions().is_empty());
+}
+
+async fn page(ctx: poise::ComponentContext<'_, Data, Error>) -> Result<(), Error> {
+    ctx.say(format!("Page handler: {}", ctx.args)).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_overlapping_component_handlers() {
+    let page_number = regex::Regex::new("page:[0-9]+:").unwrap();
+    let h = harness(poise::FrameworkOptions {
+        component_handlers: vec![
+            poise::ComponentHandler::new(poise::CustomIdPattern::Regex(page_number), |ctx| {
+                Box::pin(page(ctx))
+            }),
+            poise::ComponentHandler::new(poise::CustomIdPattern::Prefix("page:"), |ctx| {
+                Box::pin(vote(ctx))
+            }),
+        ],
+        ..options(vec![])
+    })
+    .await;
+
+    // Both handlers match, the first one wins
+    let interaction = h.component_interaction("page:3:next", None);
+    h.dispatch_interaction(serenity::Interaction::Component(interaction))
+        .await;
+    assert_eq!(take_contents(&h), ["Page handler: next"]);
+
+    // Only the second handler matches
+    let interaction = h.component_interaction("page:last", None);
+    h.dispatch_interaction(serenity::Interaction::Component(interaction))
+        .await;
+    assert_eq!(take_contents(&h), ["You voted last"]);
+}
+
+#[tokio::test]
+async fn test_blocklist() {
+    let h = harness(poise::FrameworkOptions {
+        blocklist_message: Some(|entry| format!("Blocked: {}", entry.reason.as_deref().unwrap())),
+        ..options(vec![ping()])
+    })
+    .await;
+    let target = poise::BlocklistTarget::User(h.author.id);
+    let blocklist = &h.framework().options().blocklist;
+
+    blocklist
+        .insert(poise::BlocklistEntry::new(target).reason("spam"))
+        .await;
+    h.dispatch_message(h.message("~ping")).await;
+    assert_eq!(take_contents(&h), ["Blocked: spam"]);
+
+    blocklist.remove(target).await;
+    h.dispatch_message(h.message("~ping")).await;
+    assert_eq!(take_contents(&h), ["Pong!"]);
+}
+
+#[poise::command(prefix_command, user_cooldown = 60)]
+async fn imagine(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Imagined").await?;
+    Ok(())
+}
+
+#[poise::command(prefix_command, user_cooldown = 60, cooldown_group = "imagine")]
+async fn upscale(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Upscaled").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cooldown_group_named_after_command() {
+    let h = harness(options(vec![imagine(), upscale()])).await;
+
+    // The group shares its name with a command, but not its cooldown
+    h.dispatch_message(h.message("~upscale")).await;
+    h.dispatch_message(h.message("~imagine")).await;
+    assert_eq!(take_contents(&h), ["Upscaled", "Imagined"]);
+
+    h.dispatch_message(h.message("~upscale")).await;
+    h.dispatch_message(h.message("~imagine")).await;
+    assert!(take_contents(&h).is_empty());
+    assert_eq!(error_count(&h, "CooldownHit"), 2);
+}
+
+/// Counts towards the cooldown although it fails, by starting the cooldown itself
+#[poise::command(prefix_command, user_cooldown = 60, cooldown_policy = "OnSuccess")]
+async fn charge(ctx: Context<'_>) -> Result<(), Error> {
+    poise::start_cooldown(ctx).await;
+    Err("failed".into())
+}
+
+#[poise::command(prefix_command, slash_command, user_cooldown = 60)]
+async fn add(ctx: Context<'_>, a: i32, b: i32) -> Result<(), Error> {
+    ctx.say((a + b).to_string()).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cooldown_not_recorded() {
+    let h = harness(options(vec![add()])).await;
+
+    // Invocations whose arguments fail to parse don't count
+    h.dispatch_message(h.message("~add 1 x")).await;
+    assert_eq!(error_count(&h, "ArgumentParse"), 1);
+    h.dispatch_message(h.message("~add 1 2")).await;
+    h.dispatch_message(h.message("~add 1 2")).await;
+    assert_eq!(take_contents(&h), ["3"]);
+    assert_eq!(error_count(&h, "CooldownHit"), 1);
+
+    // Neither do slash invocations which don't match the command's parameters
+    let h = harness(options(vec![add()])).await;
+    let arguments = [
+        serenity::json::json!([]),
+        serenity::json::json!([
+            { "name": "a", "type": 4, "value": 1 },
+            { "name": "b", "type": 4, "value": 2 },
+        ]),
+    ];
+    for arguments in arguments {
+        let interaction = h.command_interaction("add", arguments);
+        h.dispatch_interaction(serenity::Interaction::Command(interaction))
+            .await;
+    }
+    assert_eq!(error_count(&h, "CommandStructureMismatch"), 1);
+    assert_eq!(take_contents(&h), ["3"]);
+
+    // Neither do invocations by exempt members, which the harness' interactions are
+    let command = ping();
+    {
+        let mut config = command.cooldown_config.write().unwrap();
+        config.user = Some(Duration::from_secs(60));
+        config.exempt_permissions = serenity::Permissions::MANAGE_MESSAGES;
+    }
+    let h = harness(options(vec![command])).await;
+    for _ in 0..2 {
+        let interaction = h.command_interaction("ping", serenity::json::json!([]));
+        h.dispatch_interaction(serenity::Interaction::Command(interaction))
+            .await;
+    }
+    h.dispatch_message(h.message("~ping")).await;
+    assert_eq!(take_contents(&h), ["Pong!", "Pong!", "Pong!"]);
+    // Prefix invocations aren't exempt, since the member's permissions are unknown
+    h.dispatch_message(h.message("~ping")).await;
+    assert_eq!(error_count(&h, "CooldownHit"), 1);
+}
+
+#[tokio::test]
+async fn test_start_cooldown() {
+    let h = harness(options(vec![charge()])).await;
+    h.dispatch_message(h.message("~charge")).await;
+    h.dispatch_message(h.message("~charge")).await;
+    assert_eq!(error_count(&h, "Command"), 1);
+    assert_eq!(error_count(&h, "CooldownHit"), 1);
+}
+
+#[tokio::test]
+async fn test_timeout() {
+    let h = harness(poise::FrameworkOptions {
+        command_timeout: Some(Duration::from_millis(10)),
+        ..options(vec![sleep()])
+    })
+    .await;
+
+    h.dispatch_message(h.message("~sleep")).await;
+    assert!(take_contents(&h).is_empty());
+    assert_eq!(error_count(&h, "CommandTimeout"), 1);
+}
+
+#[tokio::test]
+async fn test_max_concurrency() {
+    let h = harness(options(vec![sleep_limited()])).await;
+    tokio::join!(
+        h.dispatch_message(h.message("~sleep")),
+        h.dispatch_message(h.message("~sleep")),
+    );
+    assert_eq!(take_contents(&h), ["Done"]);
+    assert_eq!(error_count(&h, "ConcurrencyLimit"), 1);
+
+    let h = harness(options(vec![sleep_queued()])).await;
+    tokio::join!(
+        h.dispatch_message(h.message("~sleep")),
+        h.dispatch_message(h.message("~sleep")),
+    );
+    assert_eq!(take_contents(&h), ["Done", "Done"]);
+    assert_eq!(error_count(&h, "ConcurrencyLimit"), 0);
+
+    // Queued application commands are deferred, and give up after the queue timeout
+    let mut command = sleep_queued();
+    let max_concurrency = command.max_concurrency.as_mut().unwrap();
+    max_concurrency.queue_timeout = Some(Duration::from_millis(20));
+    let h = harness(options(vec![command])).await;
+    let sleep = || {
+        let interaction = h.command_interaction("sleep", serenity::json::json!([]));
+        serenity::Interaction::Command(interaction)
+    };
+    tokio::join!(
+        h.dispatch_interaction(sleep()),
+        h.dispatch_interaction(sleep()),
+    );
+    let actions = h.take_actions();
+    assert_eq!(actions.iter().filter(|action| action.is_defer()).count(), 1);
+    assert_eq!(error_count(&h, "ConcurrencyLimit"), 1);
+
+    // Queued invocations are checked against cooldowns started while they were waiting
+    let command = sleep_queued();
+    command.cooldown_config.write().unwrap().user = Some(Duration::from_secs(60));
+    let h = harness(options(vec![command])).await;
+    tokio::join!(
+        h.dispatch_message(h.message("~sleep")),
+        h.dispatch_message(h.message("~sleep")),
+    );
+    assert_eq!(take_contents(&h), ["Done"]);
+    assert_eq!(error_count(&h, "CooldownHit"), 1);
+}
+
+#[tokio::test]
+async fn test_layers() {
+    let h = harness(poise::FrameworkOptions {
+        layers: vec![poise::Layer::new(|ctx: Context<'_>, next| {
+            Box::pin(async move {
+                ctx.data().push("before");
+                let result = next.run().await;
+                ctx.data().push("after");
+                result
+            })
+        })],
+        ..options(vec![ping(), skipped()])
+    })
+    .await;
+
+    h.dispatch_message(h.message("~ping")).await;
+    assert_eq!(h.framework().user_data.take(), ["before", "ping", "after"]);
+
+    h.dispatch_message(h.message("~skipped")).await;
+    assert_eq!(
+        h.framework().user_data.take(),
+        ["before", "skipped", "after"]
+    );
+}
+
+#[tokio::test]
+async fn test_invocation_hook_and_metrics() {
+    let h = harness(poise::FrameworkOptions {
+        on_invocation_finished: |ctx, outcome, _duration| {
+            Box::pin(async move { ctx.data().push(format!("{:?}", outcome)) })
+        },
+        ..options(vec![ping(), fail()])
+    })
+    .await;
+
+    h.dispatch_message(h.message("~ping")).await;
+    h.dispatch_message(h.message("~fail")).await;
+    h.dispatch_message(h.message("~fail")).await;
+    let events = h.framework().user_data.take();
+    assert_eq!(events, ["ping", "Success", "CommandError", "CommandError"]);
+
+    let metrics = h.framework().options().metrics.snapshot().unwrap();
+    assert_eq!(metrics.commands["ping"].invocations, 1);
+    assert_eq!(metrics.commands["ping"].failures, 0);
+    assert_eq!(metrics.commands["fail"].invocations, 2);
+    assert_eq!(metrics.commands["fail"].failures, 2);
+    assert_eq!(metrics.errors["Command"], 2);
+}
+
+#[cfg(feature = "handle_panics")]
+#[tokio::test]
+async fn test_panic_in_check() {
+    let h = harness(poise::FrameworkOptions {
+        command_check: Some(|_| Box::pin(async { panic!("check panicked") })),
+        on_invocation_finished: |ctx, outcome, _duration| {
+            Box::pin(async move { ctx.data().push(format!("{:?}", outcome)) })
+        },
+        ..options(vec![ping()])
+    })
+    .await;
+
+    h.dispatch_message(h.message("~ping")).await;
+    let interaction = h.command_interaction("ping", serenity::json::json!([]));
+    h.dispatch_interaction(serenity::Interaction::Command(interaction))
+        .await;
+    assert_eq!(h.framework().user_data.take(), ["Panic", "Panic"]);
+    assert_eq!(error_count(&h, "CommandPanic"), 2);
+}
+
+/// Span fields recorded by [`SpanRecorder`], by span
+type RecordedSpans = Arc<Mutex<Vec<(&'static tracing::Metadata<'static>, Vec<String>)>>>;
+
+/// Minimal subscriber which records the fields of all spans as `name=value`
+#[derive(Default)]
+struct SpanRecorder {
+    spans: RecordedSpans,
+    /// IDs of the currently entered spans
+    stack: Mutex<Vec<tracing::span::Id>>,
+}
+
+/// Appends visited fields to a span's fields in [`RecordedSpans`]
+struct FieldRecorder<'a>(&'a mut Vec<String>);
+
+impl tracing::field::Visit for FieldRecorder<'_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0.push(format!("{}={:?}", field.name(), value));
+    }
+}
+
+impl tracing::Subscriber for SpanRecorder {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let mut fields = Vec::new();
+        attributes.record(&mut FieldRecorder(&mut fields));
+        let mut spans = self.spans.lock().unwrap();
+        spans.push((attributes.metadata(), fields));
+        tracing::span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        values.record(&mut FieldRecorder(
+            &mut spans[span.into_u64() as usize - 1].1,
+        ));
+    }
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+    fn event(&self, _: &tracing::Event<'_>) {}
+
+    fn enter(&self, span: &tracing::span::Id) {
+        self.stack.lock().unwrap().push(span.clone());
+    }
+
+    fn exit(&self, _: &tracing::span::Id) {
+        self.stack.lock().unwrap().pop();
+    }
+
+    fn current_span(&self) -> tracing_core::span::Current {
+        match self.stack.lock().unwrap().last() {
+            Some(id) => {
+                let metadata = self.spans.lock().unwrap()[id.into_u64() as usize - 1].0;
+                tracing_core::span::Current::new(id.clone(), metadata)
+            }
+            None => tracing_core::span::Current::none(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_invocation_span() {
+    let recorder = SpanRecorder::default();
+    let spans = recorder.spans.clone();
+    let _guard = tracing::subscriber::set_default(recorder);
+
+    let h = harness(options(vec![ping(), fail()])).await;
+    h.dispatch_message(h.message("~ping")).await;
+    h.dispatch_message(h.message("~fail")).await;
+
+    let invocations = spans
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(metadata, _)| metadata.name() == "invocation")
+        .map(|(_, fields)| fields.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(invocations.len(), 2);
+    for (fields, (command, outcome)) in invocations
+        .iter()
+        .zip([("ping", "Success"), ("fail", "CommandError")])
+    {
+        assert!(fields.contains(&format!("command={}", command)));
+        assert!(fields.contains(&format!("outcome={}", outcome)));
+        assert!(fields.contains(&format!("user_id={}", h.author.id)));
+    }
+}
+
+#[tokio::test]
+async fn test_registry() {
+    let h = harness(options(vec![fail()])).await;
+    let registry = h.framework().registry();
+
+    registry.add(ping());
+    registry.remove("fail");
+    h.dispatch_message(h.message("~ping")).await;
+    h.dispatch_message(h.message("~fail")).await;
+    assert_eq!(take_contents(&h), ["Pong!"]);
+    assert_eq!(error_count(&h, "Command"), 0);
+
+    registry.disable("ping", h.guild_id);
+    h.dispatch_message(h.message("~ping")).await;
+    assert_eq!(error_count(&h, "CommandDisabled"), 1);
+    registry.enable("ping", h.guild_id);
+
+    let interaction = h.command_interaction("ping", serenity::json::json!([]));
+    h.dispatch_interaction(serenity::Interaction::Command(interaction))
+        .await;
+    assert_eq!(take_contents(&h), ["Pong!"]);
+}
+
+#[tokio::test]
+async fn test_help_hides_disabled_commands() {
+    let h = harness(options(vec![help(), ping()])).await;
+    h.framework().registry().disable("ping", h.guild_id);
+
+    h.dispatch_message(h.message("~help")).await;
+    h.dispatch_message(h.message("~help ping")).await;
+    let contents = take_contents(&h);
+    assert!(contents[0].contains("~help") && !contents[0].contains("~ping"));
+    assert_eq!(contents[1], "No such command `ping`");
+}